@@ -0,0 +1,152 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Variant};
+
+struct VariantArgs {
+    error_type: LitStr,
+    code: LitStr,
+}
+
+fn parse_variant_args(variant: &Variant) -> syn::Result<VariantArgs> {
+    let mut error_type = None;
+    let mut code = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("event_error") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                error_type = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("code") {
+                code = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `type` or `code`"))
+            }
+        })?;
+    }
+
+    match (error_type, code) {
+        (Some(error_type), Some(code)) => Ok(VariantArgs { error_type, code }),
+        _ => Err(Error::new(
+            variant.span(),
+            "expected `#[event_error(type = \"...\", code = \"...\")]`",
+        )),
+    }
+}
+
+fn camel_case(ident: &syn::Ident) -> String {
+    let name = ident.to_string();
+    let mut parts = name
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|p| !p.is_empty());
+    let mut key = String::from(parts.next().unwrap_or_default());
+    for part in parts {
+        let mut chars = part.chars();
+        key.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        key.push_str(chars.as_str());
+    }
+    key
+}
+
+fn error_type_check(error_type: &LitStr) -> TokenStream {
+    let message = LitStr::new(
+        &format!(
+            "unknown error type `{}`, expected one of events_protocol::errors::ERROR_TYPES",
+            error_type.value()
+        ),
+        error_type.span(),
+    );
+    quote_spanned! {error_type.span()=>
+        const _: () = ::std::assert!(
+            ::events_protocol::errors::is_error_type(#error_type),
+            #message
+        );
+    }
+}
+
+fn variant_arm(
+    enum_ident: &syn::Ident,
+    variant: &Variant,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let VariantArgs { error_type, code } = parse_variant_args(variant)?;
+    let ident = &variant.ident;
+
+    let (pattern, parameters) = match &variant.fields {
+        Fields::Named(fields) => {
+            let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+            let keys = names.iter().map(|name| camel_case(name.as_ref().unwrap()));
+            (
+                quote!(#enum_ident::#ident { #(#names),* }),
+                quote!(::events_protocol::__private::serde_json::json!({ #(#keys: #names),* })),
+            )
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => (
+            quote!(#enum_ident::#ident(value)),
+            quote!(::events_protocol::__private::serde_json::json!(value)),
+        ),
+        Fields::Unnamed(fields) => {
+            let names: Vec<_> = (0..fields.unnamed.len())
+                .map(|idx| format_ident!("value{}", idx))
+                .collect();
+            (
+                quote!(#enum_ident::#ident(#(#names),*)),
+                quote!(::events_protocol::__private::serde_json::json!([#(#names),*])),
+            )
+        }
+        Fields::Unit => (
+            quote!(#enum_ident::#ident),
+            quote!(::events_protocol::__private::serde_json::json!({})),
+        ),
+    };
+
+    Ok((
+        quote! {
+            #pattern => (#error_type, #code, #parameters)
+        },
+        error_type_check(&error_type),
+    ))
+}
+
+pub fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let ident = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "EventError can only be derived for enums",
+            ))
+        }
+    };
+    let (arms, checks): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|variant| variant_arm(ident, variant))
+        .collect::<syn::Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #(#checks)*
+
+        impl #impl_generics ::std::convert::From<#ident #ty_generics>
+            for ::events_protocol::errors::EventErrorType #where_clause
+        {
+            fn from(error: #ident #ty_generics) -> Self {
+                let (error_type, code, parameters) = match error {
+                    #(#arms),*
+                };
+                ::events_protocol::errors::EventErrorType::new(
+                    error_type,
+                    ::events_protocol::errors::EventError::new(code, parameters),
+                )
+            }
+        }
+    })
+}
@@ -0,0 +1,206 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::spanned::Spanned;
+use syn::{Error, FnArg, Ident, ItemFn, LitInt, LitStr, PatType, Type};
+
+struct HandlerArgs {
+    name: LitStr,
+    version: LitInt,
+}
+
+fn parse_args(attr: TokenStream) -> syn::Result<HandlerArgs> {
+    let mut name = None;
+    let mut version = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse::<LitStr>()?);
+            Ok(())
+        } else if meta.path.is_ident("version") {
+            let lit = meta.value()?.parse::<LitInt>()?;
+            lit.base10_parse::<u16>()?;
+            version = Some(lit);
+            Ok(())
+        } else {
+            Err(meta.error("expected `name` or `version`"))
+        }
+    });
+    parser.parse2(attr.clone())?;
+
+    match (name, version) {
+        (Some(name), Some(version)) => Ok(HandlerArgs { name, version }),
+        (None, _) => Err(Error::new(attr.span(), "missing `name = \"...\"`")),
+        (_, None) => Err(Error::new(attr.span(), "missing `version = ...`")),
+    }
+}
+
+enum Input {
+    Context,
+    Event,
+    Payload(Box<Type>),
+}
+
+fn take_marker(typed: &mut PatType) -> syn::Result<Option<Input>> {
+    let mut marker = None;
+    let mut result = Ok(());
+    typed.attrs.retain(|attr| {
+        let input = if attr.path().is_ident("event") {
+            Input::Event
+        } else if attr.path().is_ident("context") {
+            Input::Context
+        } else {
+            return true;
+        };
+        if marker.is_some() {
+            result = Err(Error::new(
+                attr.span(),
+                "an argument can be either `#[event]` or `#[context]`",
+            ));
+        } else if let Err(err) = attr.meta.require_path_only() {
+            result = Err(err);
+        }
+        marker = Some(input);
+        false
+    });
+    result.map(|_| marker)
+}
+
+fn classify_inputs(item: &mut ItemFn) -> syn::Result<Vec<Input>> {
+    let mut inputs = vec![];
+    for arg in &mut item.sig.inputs {
+        let typed = match arg {
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(
+                    receiver.span(),
+                    "event handlers cannot take `self`",
+                ))
+            }
+            FnArg::Typed(typed) => typed,
+        };
+        let marker = take_marker(typed)?;
+        match (marker, &*typed.ty) {
+            (Some(input), Type::Reference(_)) => inputs.push(input),
+            (Some(_), ty) => {
+                return Err(Error::new(
+                    ty.span(),
+                    "`#[event]` and `#[context]` arguments must be references",
+                ))
+            }
+            (None, Type::Reference(reference)) => {
+                return Err(Error::new(
+                    reference.span(),
+                    "mark the request event with `#[event]` or the context with `#[context]`",
+                ))
+            }
+            (None, ty) => inputs.push(Input::Payload(Box::new(ty.clone()))),
+        }
+    }
+
+    let count = |kind: fn(&Input) -> bool| inputs.iter().filter(|input| kind(input)).count();
+    if count(|input| matches!(input, Input::Payload(_))) > 1 {
+        return Err(Error::new(
+            item.sig.inputs.span(),
+            "event handlers take at most one payload argument",
+        ));
+    }
+    if count(|input| matches!(input, Input::Event)) > 1
+        || count(|input| matches!(input, Input::Context)) > 1
+    {
+        return Err(Error::new(
+            item.sig.inputs.span(),
+            "event handlers take at most one `#[event]` and one `#[context]` argument",
+        ));
+    }
+    Ok(inputs)
+}
+
+fn handler_ident(ident: &Ident) -> Ident {
+    let mut name = String::new();
+    for part in ident.to_string().split('_').filter(|part| !part.is_empty()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    format_ident!("{}Handler", name, span = ident.span())
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = parse_args(attr)?;
+    let mut item: ItemFn = syn::parse2(item)?;
+
+    if let Some(asyncness) = &item.sig.asyncness {
+        return Err(Error::new(
+            asyncness.span(),
+            "event handlers cannot be async",
+        ));
+    }
+    if !item.sig.generics.params.is_empty() {
+        return Err(Error::new(
+            item.sig.generics.span(),
+            "event handlers cannot be generic",
+        ));
+    }
+
+    let inputs = classify_inputs(&mut item)?;
+    let call_args = inputs.iter().map(|input| match input {
+        Input::Context => quote!(context),
+        Input::Event => quote!(event),
+        Input::Payload(ty) => {
+            quote!(::events_protocol::handlers::payload_of::<#ty>(event)?)
+        }
+    });
+
+    let vis = &item.vis;
+    let ident = &item.sig.ident;
+    let handler = handler_ident(ident);
+    let name = &args.name;
+    let version = &args.version;
+
+    Ok(quote! {
+        #item
+
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #handler;
+
+        impl ::events_protocol::handlers::EventHandler for #handler {
+            fn handle(
+                &self,
+                event: &::events_protocol::events::RequestEvent,
+            ) -> ::std::result::Result<
+                ::events_protocol::events::ResponseEvent,
+                ::events_protocol::errors::EventErrorType,
+            > {
+                self.handle_with_context(
+                    &::events_protocol::context::EventContext::detached(event),
+                    event,
+                )
+            }
+
+            #[allow(unused_variables)]
+            fn handle_with_context(
+                &self,
+                context: &::events_protocol::context::EventContext,
+                event: &::events_protocol::events::RequestEvent,
+            ) -> ::std::result::Result<
+                ::events_protocol::events::ResponseEvent,
+                ::events_protocol::errors::EventErrorType,
+            > {
+                match #ident(#(#call_args),*) {
+                    ::std::result::Result::Ok(payload) => ::std::result::Result::Ok(
+                        ::events_protocol::events::response_for(event, payload),
+                    ),
+                    ::std::result::Result::Err(err) => {
+                        ::std::result::Result::Err(::std::convert::Into::into(err))
+                    }
+                }
+            }
+        }
+
+        impl ::events_protocol::handlers::NamedEventHandler for #handler {
+            const NAME: &'static str = #name;
+            const VERSION: u16 = #version;
+        }
+    })
+}
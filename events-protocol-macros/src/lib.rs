@@ -0,0 +1,20 @@
+extern crate proc_macro;
+
+mod event_error;
+mod event_handler;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+pub fn event_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    event_handler::expand(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(EventError, attributes(event_error))]
+pub fn derive_event_error(input: TokenStream) -> TokenStream {
+    event_error::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
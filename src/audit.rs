@@ -0,0 +1,255 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::events::{epoch_millis, RequestEvent, ResponseEvent};
+
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub timestamp: u64,
+    pub name: String,
+    pub version: u16,
+    pub id: Uuid,
+    pub flow_id: Uuid,
+    pub identity: Map<String, Value>,
+    pub outcome: String,
+}
+
+pub trait AuditSink: Send + Sync {
+    fn audit(&self, record: &AuditRecord) -> io::Result<()>;
+}
+
+pub struct Auditor {
+    sink: Box<dyn AuditSink>,
+    identity_fields: Vec<String>,
+}
+
+impl Auditor {
+    pub fn new(sink: Box<dyn AuditSink>) -> Self {
+        Auditor {
+            sink,
+            identity_fields: vec![],
+        }
+    }
+
+    pub fn with_identity_fields(mut self, fields: &[&str]) -> Self {
+        self.identity_fields = fields.iter().map(|f| String::from(*f)).collect();
+        self
+    }
+
+    pub fn audit(&self, event: &RequestEvent, response: &ResponseEvent) {
+        let identity = self
+            .identity_fields
+            .iter()
+            .filter_map(|field| {
+                event
+                    .identity
+                    .get(field)
+                    .map(|value| (field.clone(), value.clone()))
+            })
+            .collect();
+
+        let record = AuditRecord {
+            timestamp: epoch_millis(),
+            name: event.name.clone(),
+            version: event.version,
+            id: event.id,
+            flow_id: event.flow_id,
+            identity,
+            outcome: String::from(response.error_type().unwrap_or("success")),
+        };
+        if let Err(err) = self.sink.audit(&record) {
+            log::error!("Could not audit event {}: {}", event.name, err);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChainedEntry {
+    record: AuditRecord,
+    previous_hash: String,
+    hash: String,
+}
+
+fn chain_hash(previous_hash: &str, record: &AuditRecord) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(serde_json::to_vec(record)?);
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub struct HashChainedFileSink {
+    state: Mutex<(File, String)>,
+}
+
+impl HashChainedFileSink {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let last_hash = if path.as_ref().exists() {
+            read_chain(File::open(&path)?)?
+        } else {
+            String::from(GENESIS_HASH)
+        };
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(HashChainedFileSink {
+            state: Mutex::new((file, last_hash)),
+        })
+    }
+}
+
+impl AuditSink for HashChainedFileSink {
+    fn audit(&self, record: &AuditRecord) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let (file, previous_hash) = &mut *state;
+
+        let entry = ChainedEntry {
+            record: record.clone(),
+            previous_hash: previous_hash.clone(),
+            hash: chain_hash(previous_hash, record)?,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.flush()?;
+
+        *previous_hash = entry.hash;
+        Ok(())
+    }
+}
+
+pub fn verify_chain<P: AsRef<Path>>(path: P) -> io::Result<usize> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut verified = 0;
+    let mut previous_hash = String::from(GENESIS_HASH);
+    for (idx, line) in reader.lines().enumerate() {
+        let entry: ChainedEntry = serde_json::from_str(&line?)?;
+        if entry.previous_hash != previous_hash
+            || entry.hash != chain_hash(&previous_hash, &entry.record)?
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("audit chain broken at line {}", idx + 1),
+            ));
+        }
+        previous_hash = entry.hash;
+        verified += 1;
+    }
+    Ok(verified)
+}
+
+fn read_chain(file: File) -> io::Result<String> {
+    let mut last_hash = String::from(GENESIS_HASH);
+    for line in BufReader::new(file).lines() {
+        let entry: ChainedEntry = serde_json::from_str(&line?)?;
+        last_hash = entry.hash;
+    }
+    Ok(last_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::audit::{verify_chain, Auditor, HashChainedFileSink};
+    use crate::errors::EventError;
+    use crate::errors::EventErrorType::Forbidden;
+    use crate::events::response_for;
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+
+    fn raw_event(name: &str) -> String {
+        format!(
+            r#"{{
+                    "name": "{}",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {{}},
+                    "metadata": {{}},
+                    "identity": {{ "userId": 42, "email": "user@example.com" }},
+                    "auth": {{}}
+                }}"#,
+            name
+        )
+    }
+
+    #[test]
+    fn test_audits_only_auditable_events() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", Uuid::new_v4()));
+
+        let mut store = SimpleEventStore::new();
+        store.add("event:test", 1, |req| Ok(response_for(req, "ok")));
+        store
+            .add("account:delete", 1, |_req| {
+                Err(Forbidden(EventError::new("NOT_OWNER", json!({}))))
+            })
+            .auditable();
+        store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .auditable();
+
+        let auditor = Auditor::new(Box::new(HashChainedFileSink::open(&path).unwrap()))
+            .with_identity_fields(&["userId"]);
+        let event_processor = EventProcessor::new(Box::new(store)).with_auditor(auditor);
+
+        event_processor.process_event(&raw_event("event:test"));
+        event_processor.process_event(&raw_event("account:delete"));
+        event_processor.process_event(&raw_event("account:get"));
+
+        assert_eq!(2, verify_chain(&path).unwrap());
+
+        let content = fs::read_to_string(&path).unwrap();
+        let entries: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!("account:delete", entries[0]["record"]["name"]);
+        assert_eq!("forbidden", entries[0]["record"]["outcome"]);
+        assert_eq!(json!({"userId": 42}), entries[0]["record"]["identity"]);
+        assert_eq!("success", entries[1]["record"]["outcome"]);
+        assert_eq!(entries[0]["hash"], entries[1]["previousHash"]);
+    }
+
+    #[test]
+    fn test_detects_tampered_chain() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", Uuid::new_v4()));
+
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .auditable();
+        let event_processor = EventProcessor::new(Box::new(store)).with_auditor(Auditor::new(
+            Box::new(HashChainedFileSink::open(&path).unwrap()),
+        ));
+
+        event_processor.process_event(&raw_event("account:get"));
+        event_processor.process_event(&raw_event("account:get"));
+        assert_eq!(2, verify_chain(&path).unwrap());
+
+        let tampered =
+            fs::read_to_string(&path)
+                .unwrap()
+                .replacen("\"success\"", "\"forbidden\"", 1);
+        fs::write(&path, tampered).unwrap();
+
+        let error = verify_chain(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!("audit chain broken at line 1", error.to_string());
+    }
+}
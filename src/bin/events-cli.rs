@@ -0,0 +1,198 @@
+use std::fs;
+use std::process;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+use uuid::Uuid;
+
+use events_protocol::client::http::HttpEventClient;
+use events_protocol::client::process::ProcessEventClient;
+use events_protocol::client::EventClient;
+use events_protocol::errors::{ERROR_TYPES, PROTOCOL_ERROR_TYPES};
+use events_protocol::events::{RequestEvent, ResponseEvent};
+use events_protocol::validation::{validate_document, EventKind};
+
+const EXIT_INVALID_INPUT: i32 = 1;
+const EXIT_TRANSPORT_ERROR: i32 = 3;
+
+#[derive(Parser)]
+#[command(
+    name = "events-cli",
+    about = "Send and inspect events protocol messages"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Send(SendArgs),
+    Validate(ValidateArgs),
+}
+
+#[derive(Args)]
+struct SendArgs {
+    #[arg(long)]
+    name: String,
+    #[arg(long, default_value_t = 1)]
+    version: u16,
+    #[arg(long, conflicts_with = "payload_file")]
+    payload: Option<String>,
+    #[arg(long)]
+    payload_file: Option<String>,
+    #[arg(long, default_value = "{}")]
+    identity: String,
+    #[arg(long, default_value = "{}")]
+    auth: String,
+    #[arg(long, default_value = "{}")]
+    metadata: String,
+    #[arg(long)]
+    id: Option<Uuid>,
+    #[arg(long)]
+    flow_id: Option<Uuid>,
+    #[arg(long, required_unless_present = "exec", conflicts_with = "exec")]
+    url: Option<String>,
+    #[arg(
+        long,
+        num_args = 1..,
+        allow_hyphen_values = true,
+        value_name = "COMMAND",
+        help = "Run COMMAND as a local processor; every argument after it is passed to COMMAND"
+    )]
+    exec: Option<Vec<String>>,
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    #[arg(long, value_enum, default_value_t = Kind::Auto)]
+    kind: Kind,
+    #[arg(required = true)]
+    files: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    Auto,
+    Request,
+    Response,
+}
+
+fn parse_json(field: &str, raw: &str) -> Result<Value, String> {
+    serde_json::from_str(raw).map_err(|err| format!("invalid JSON for --{}: {}", field, err))
+}
+
+fn build_event(args: &SendArgs) -> Result<RequestEvent, String> {
+    let payload = match (&args.payload, &args.payload_file) {
+        (Some(raw), _) => parse_json("payload", raw)?,
+        (None, Some(path)) => {
+            let raw = fs::read_to_string(path)
+                .map_err(|err| format!("could not read {}: {}", path, err))?;
+            parse_json("payload-file", &raw)?
+        }
+        (None, None) => Value::Object(Default::default()),
+    };
+
+    Ok(RequestEvent {
+        name: args.name.clone(),
+        version: args.version,
+        id: args.id.unwrap_or_else(Uuid::new_v4),
+        flow_id: args.flow_id.unwrap_or_else(Uuid::new_v4),
+        payload,
+        identity: parse_json("identity", &args.identity)?,
+        auth: parse_json("auth", &args.auth)?,
+        metadata: parse_json("metadata", &args.metadata)?,
+    })
+}
+
+fn exit_code(response: &ResponseEvent) -> i32 {
+    let error_type = match response.error_type() {
+        Some(error_type) => error_type,
+        None => return 0,
+    };
+    let mut error_types = ERROR_TYPES.iter().chain(PROTOCOL_ERROR_TYPES.iter());
+    let position = error_types
+        .position(|known| *known == error_type)
+        .unwrap_or(ERROR_TYPES.len() + PROTOCOL_ERROR_TYPES.len());
+    10 + position as i32
+}
+
+fn send(args: SendArgs) -> i32 {
+    let event = match build_event(&args) {
+        Ok(event) => event,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_INVALID_INPUT;
+        }
+    };
+
+    let client: Box<dyn EventClient> = match (&args.url, &args.exec) {
+        (Some(url), _) => Box::new(HttpEventClient::new(url)),
+        (None, Some(command)) => {
+            let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
+            match ProcessEventClient::spawn(&command[0], &args) {
+                Ok(client) => Box::new(client),
+                Err(err) => {
+                    eprintln!("could not start '{}': {}", command.join(" "), err);
+                    return EXIT_TRANSPORT_ERROR;
+                }
+            }
+        }
+        (None, None) => unreachable!("clap requires --url or --exec"),
+    };
+
+    match client.send(&event) {
+        Ok(response) => {
+            println!("{}", serde_json::to_string_pretty(&response).unwrap());
+            exit_code(&response)
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            EXIT_TRANSPORT_ERROR
+        }
+    }
+}
+
+fn validate(args: ValidateArgs) -> i32 {
+    let kind = match args.kind {
+        Kind::Auto => None,
+        Kind::Request => Some(EventKind::Request),
+        Kind::Response => Some(EventKind::Response),
+    };
+
+    let mut code = 0;
+    for path in &args.files {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                code = EXIT_INVALID_INPUT;
+                continue;
+            }
+        };
+        for problem in validate_document(&contents, kind) {
+            code = EXIT_INVALID_INPUT;
+            if problem.pointer.is_empty() {
+                eprintln!(
+                    "{}:{}:{}: {}",
+                    path, problem.line, problem.column, problem.message
+                );
+            } else {
+                eprintln!(
+                    "{}:{}:{}: {}: {}",
+                    path, problem.line, problem.column, problem.pointer, problem.message
+                );
+            }
+        }
+    }
+    code
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let code = match cli.command {
+        Command::Send(args) => send(args),
+        Command::Validate(args) => validate(args),
+    };
+    process::exit(code);
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Mutex;
+
+use serde_json::json;
+
+use crate::client::EventClient;
+use crate::errors::EventErrorType;
+use crate::events::RequestEvent;
+use crate::processor::{notify_listeners, NotificationReport};
+use crate::store::EventStore;
+
+pub trait BusBridge: Send + Sync {
+    fn accepts(&self, _event: &RequestEvent) -> bool {
+        true
+    }
+
+    fn forward(&self, event: &RequestEvent) -> Result<(), EventErrorType>;
+}
+
+pub struct ClientBridge<C: EventClient> {
+    client: C,
+}
+
+impl<C: EventClient> ClientBridge<C> {
+    pub fn new(client: C) -> Self {
+        ClientBridge { client }
+    }
+}
+
+impl<C: EventClient + Send + Sync> BusBridge for ClientBridge<C> {
+    fn forward(&self, event: &RequestEvent) -> Result<(), EventErrorType> {
+        match self.client.send(event) {
+            Ok(response) => match response.error() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            },
+            Err(err) => Err(EventErrorType::generic(
+                "BRIDGE_UNAVAILABLE",
+                json!({ "endpoint": self.client.endpoint(), "message": format!("{}", err) }),
+            )
+            .with_source(err)),
+        }
+    }
+}
+
+type Subscribers = HashMap<(String, u16), Vec<(u64, SyncSender<RequestEvent>)>>;
+
+pub struct EventBus {
+    store: Box<dyn EventStore + Send + Sync>,
+    channels: Mutex<(u64, Subscribers)>,
+    bridges: Vec<Box<dyn BusBridge>>,
+}
+
+impl EventBus {
+    pub fn new(store: Box<dyn EventStore + Send + Sync>) -> Self {
+        EventBus {
+            store,
+            channels: Mutex::new((0, HashMap::new())),
+            bridges: vec![],
+        }
+    }
+
+    pub fn with_bridge(mut self, bridge: Box<dyn BusBridge>) -> Self {
+        self.bridges.push(bridge);
+        self
+    }
+
+    pub fn subscribe_channel(
+        &self,
+        name: &str,
+        version: u16,
+        capacity: usize,
+    ) -> Receiver<RequestEvent> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let mut channels = self.channels.lock().unwrap();
+        let (next_id, subscribers) = &mut *channels;
+        *next_id += 1;
+        subscribers
+            .entry((String::from(name), version))
+            .or_default()
+            .push((*next_id, sender));
+        receiver
+    }
+
+    pub fn publish(&self, event: &RequestEvent) -> NotificationReport {
+        let mut report = notify_listeners(self.store.as_ref(), event);
+
+        let key = (event.name.clone(), event.version);
+        let senders = self
+            .channels
+            .lock()
+            .unwrap()
+            .1
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+        let mut disconnected = vec![];
+        for (id, sender) in senders {
+            match sender.send(event.clone()) {
+                Ok(()) => report.delivered += 1,
+                Err(_) => disconnected.push(id),
+            }
+        }
+        if !disconnected.is_empty() {
+            if let Some(subscribers) = self.channels.lock().unwrap().1.get_mut(&key) {
+                subscribers.retain(|(id, _)| !disconnected.contains(id));
+            }
+        }
+
+        for bridge in self.bridges.iter().filter(|bridge| bridge.accepts(event)) {
+            if let Err(err) = bridge.forward(event) {
+                log::warn!(
+                    "Bridge for {} v{} (flow {}) failed with {}",
+                    event.name,
+                    event.version,
+                    event.flow_id,
+                    err
+                );
+                report.failures.push(err);
+            } else {
+                report.delivered += 1;
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::TryRecvError;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::bus::{BusBridge, ClientBridge, EventBus};
+    use crate::errors::EventErrorType;
+    use crate::events::{child_event, new_event, response_for, RequestEvent};
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+
+    struct RecordingBridge {
+        forwarded: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl BusBridge for RecordingBridge {
+        fn accepts(&self, event: &RequestEvent) -> bool {
+            event.name.starts_with("order:")
+        }
+
+        fn forward(&self, event: &RequestEvent) -> Result<(), EventErrorType> {
+            self.forwarded.lock().unwrap().push(event.name.clone());
+            Err(EventErrorType::generic("BRIDGE_DOWN", json!({})))
+        }
+    }
+
+    #[test]
+    fn test_delivers_to_listeners_channels_and_bridges() {
+        let received = Arc::new(Mutex::new(vec![]));
+        let mut store = SimpleEventStore::new_sync();
+        {
+            let received = received.clone();
+            store.add_listener("order:placed", 1, move |event| {
+                received.lock().unwrap().push(event.flow_id);
+                Ok(())
+            });
+        }
+        let forwarded = Arc::new(Mutex::new(vec![]));
+        let bus = EventBus::new(Box::new(store)).with_bridge(Box::new(RecordingBridge {
+            forwarded: forwarded.clone(),
+        }));
+        let channel = bus.subscribe_channel("order:placed", 1, 4);
+
+        let parent = new_event("checkout", 1, json!({}));
+        let event = child_event(&parent, "order:placed", 1, json!({ "order": 1 }));
+        let report = bus.publish(&event);
+
+        assert_eq!(1, report.listeners);
+        assert_eq!(2, report.delivered);
+        assert_eq!("BRIDGE_DOWN", report.failures[0].code());
+        assert_eq!(vec![parent.flow_id], *received.lock().unwrap());
+        let delivered = channel.try_recv().unwrap();
+        assert_eq!(parent.flow_id, delivered.flow_id);
+        assert_ne!(parent.id, delivered.id);
+        assert_eq!(vec!["order:placed"], *forwarded.lock().unwrap());
+
+        assert_eq!(0, bus.publish(&new_event("user:created", 1, ())).delivered);
+    }
+
+    #[test]
+    fn test_client_bridges_report_malformed_error_responses() {
+        let mut remote = SimpleEventStore::new_sync();
+        remote.add("order:placed", 1, |req| {
+            let mut response = response_for(req, "boom");
+            response.name = String::from("order:placed:error");
+            Ok(response)
+        });
+        let bridge = ClientBridge::new(EventProcessor::new_sync(Box::new(remote)));
+        let bus =
+            EventBus::new(Box::new(SimpleEventStore::new_sync())).with_bridge(Box::new(bridge));
+
+        let report = bus.publish(&new_event("order:placed", 1, ()));
+
+        assert_eq!(0, report.delivered);
+        assert_eq!("MALFORMED_ERROR_RESPONSE", report.failures[0].code());
+    }
+
+    #[test]
+    fn test_bounded_channels_apply_back_pressure() {
+        let bus = EventBus::new(Box::new(SimpleEventStore::new_sync()));
+        let channel = bus.subscribe_channel("tick", 1, 1);
+
+        thread::scope(|scope| {
+            let publisher = scope.spawn(|| {
+                for idx in 0..3 {
+                    bus.publish(&new_event("tick", 1, idx));
+                }
+            });
+
+            thread::sleep(Duration::from_millis(50));
+            assert!(!publisher.is_finished());
+            for idx in 0..3 {
+                assert_eq!(json!(idx), channel.recv().unwrap().payload);
+            }
+            publisher.join().unwrap();
+        });
+        assert!(matches!(channel.try_recv(), Err(TryRecvError::Empty)));
+
+        drop(channel);
+        assert_eq!(0, bus.publish(&new_event("tick", 1, 3)).delivered);
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::events::{RequestEvent, ResponseEvent};
+
+pub mod framed;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod process;
+pub mod scatter;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Transport(String),
+    CircuitOpen(String),
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Transport(message) => write!(f, "transport error: {}", message),
+            ClientError::CircuitOpen(key) => write!(f, "circuit breaker open for: {}", key),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+pub trait EventClient {
+    fn endpoint(&self) -> &str;
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+    pub retryable_error_types: Vec<String>,
+}
+
+impl RetryPolicy {
+    pub fn no_retry() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_retryable(&self, error_type: &str) -> bool {
+        self.retryable_error_types
+            .iter()
+            .any(|retryable| retryable == error_type)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential =
+            self.initial_backoff.as_secs_f64() * self.multiplier.powi(attempt as i32 - 1);
+        let capped = exponential.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::thread_rng().gen::<f64>();
+        Duration::from_secs_f64(capped * (1.0 - jitter))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: 0.5,
+            retryable_error_types: vec![String::from("error")],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerScope {
+    Endpoint,
+    Event,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerPolicy {
+    pub failure_threshold: u32,
+    pub open_duration: Duration,
+    pub scope: BreakerScope,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        CircuitBreakerPolicy {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+            scope: BreakerScope::Event,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakerStatus {
+    pub key: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+}
+
+struct CircuitBreaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new() -> Self {
+        CircuitBreaker {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_started_at: None,
+        }
+    }
+
+    fn try_acquire(&mut self, policy: &CircuitBreakerPolicy, now: Instant) -> bool {
+        match self.state {
+            BreakerState::Closed => true,
+            BreakerState::HalfOpen => {
+                let probe_expired = self.probe_started_at.is_none_or(|started_at| {
+                    now.saturating_duration_since(started_at) >= policy.open_duration
+                });
+                if probe_expired {
+                    self.probe_started_at = Some(now);
+                }
+                probe_expired
+            }
+            BreakerState::Open => {
+                let opened_at = self.opened_at.unwrap_or(now);
+                if now.saturating_duration_since(opened_at) >= policy.open_duration {
+                    self.state = BreakerState::HalfOpen;
+                    self.probe_started_at = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn on_success(&mut self) {
+        self.state = BreakerState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.probe_started_at = None;
+    }
+
+    fn on_failure(&mut self, policy: &CircuitBreakerPolicy, now: Instant) {
+        self.consecutive_failures += 1;
+        self.probe_started_at = None;
+        if self.state == BreakerState::HalfOpen
+            || self.consecutive_failures >= policy.failure_threshold
+        {
+            self.state = BreakerState::Open;
+            self.opened_at = Some(now);
+        }
+    }
+}
+
+pub struct ResilientClient<C: EventClient> {
+    client: C,
+    retry_policy: RetryPolicy,
+    breaker_policy: CircuitBreakerPolicy,
+    breakers: Mutex<HashMap<String, CircuitBreaker>>,
+}
+
+impl<C: EventClient> ResilientClient<C> {
+    pub fn new(client: C) -> Self {
+        ResilientClient {
+            client,
+            retry_policy: RetryPolicy::default(),
+            breaker_policy: CircuitBreakerPolicy::default(),
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker_policy: CircuitBreakerPolicy) -> Self {
+        self.breaker_policy = breaker_policy;
+        self
+    }
+
+    pub fn breaker_state(&self, event_name: &str, version: u16) -> BreakerState {
+        let key = self.breaker_key(event_name, version);
+        self.breakers
+            .lock()
+            .unwrap()
+            .get(&key)
+            .map(|breaker| breaker.state)
+            .unwrap_or(BreakerState::Closed)
+    }
+
+    pub fn breaker_states(&self) -> Vec<BreakerStatus> {
+        self.breakers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, breaker)| BreakerStatus {
+                key: key.clone(),
+                state: breaker.state,
+                consecutive_failures: breaker.consecutive_failures,
+            })
+            .collect()
+    }
+
+    fn breaker_key(&self, event_name: &str, version: u16) -> String {
+        match self.breaker_policy.scope {
+            BreakerScope::Endpoint => String::from(self.client.endpoint()),
+            BreakerScope::Event => {
+                format!("{} {} v{}", self.client.endpoint(), event_name, version)
+            }
+        }
+    }
+
+    fn acquire(&self, key: &str) -> bool {
+        let mut breakers = self.breakers.lock().unwrap();
+        breakers
+            .entry(String::from(key))
+            .or_insert_with(CircuitBreaker::new)
+            .try_acquire(&self.breaker_policy, Instant::now())
+    }
+
+    fn record(&self, key: &str, failed: bool) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers
+            .entry(String::from(key))
+            .or_insert_with(CircuitBreaker::new);
+        if failed {
+            breaker.on_failure(&self.breaker_policy, Instant::now());
+        } else {
+            breaker.on_success();
+        }
+    }
+
+    fn is_retryable(&self, result: &Result<ResponseEvent, ClientError>) -> bool {
+        match result {
+            Ok(response) => response
+                .error_type()
+                .is_some_and(|error_type| self.retry_policy.is_retryable(error_type)),
+            Err(ClientError::Transport(_)) => true,
+            Err(ClientError::CircuitOpen(_)) => false,
+        }
+    }
+}
+
+impl<C: EventClient> EventClient for ResilientClient<C> {
+    fn endpoint(&self) -> &str {
+        self.client.endpoint()
+    }
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+        let key = self.breaker_key(&event.name, event.version);
+        let mut attempt = 0;
+        let mut last_result = None;
+        loop {
+            attempt += 1;
+            if !self.acquire(&key) {
+                return last_result.unwrap_or(Err(ClientError::CircuitOpen(key)));
+            }
+
+            let result = self.client.send(event);
+            let retryable = self.is_retryable(&result);
+            self.record(&key, retryable);
+
+            if !retryable || attempt >= self.retry_policy.max_attempts {
+                return result;
+            }
+            last_result = Some(result);
+            thread::sleep(self.retry_policy.backoff(attempt));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::client::{
+        BreakerScope, BreakerState, CircuitBreaker, CircuitBreakerPolicy, ClientError, EventClient,
+        ResilientClient, RetryPolicy,
+    };
+    use crate::errors::{error_for, EventError, EventErrorType};
+    use crate::events::{parse_event, response_for, RequestEvent, ResponseEvent};
+
+    enum Outcome {
+        Success,
+        Failure(&'static str),
+        Unreachable,
+    }
+
+    struct ScriptedClient {
+        outcomes: RefCell<VecDeque<Outcome>>,
+        received_ids: RefCell<Vec<Uuid>>,
+    }
+
+    impl ScriptedClient {
+        fn new(outcomes: Vec<Outcome>) -> Self {
+            ScriptedClient {
+                outcomes: RefCell::new(outcomes.into_iter().collect()),
+                received_ids: RefCell::new(vec![]),
+            }
+        }
+    }
+
+    impl EventClient for ScriptedClient {
+        fn endpoint(&self) -> &str {
+            "http://localhost/events"
+        }
+
+        fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+            self.received_ids.borrow_mut().push(event.id);
+            match self.outcomes.borrow_mut().pop_front() {
+                Some(Outcome::Success) | None => Ok(response_for(event, "ok")),
+                Some(Outcome::Failure(error_type)) => Ok(error_for(
+                    event,
+                    &EventErrorType::new(error_type, EventError::new("SOME_ERROR", json!({}))),
+                )),
+                Some(Outcome::Unreachable) => {
+                    Err(ClientError::Transport(String::from("connection refused")))
+                }
+            }
+        }
+    }
+
+    fn event() -> RequestEvent {
+        parse_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap()
+    }
+
+    fn retry_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_retries_retryable_errors_with_same_id() {
+        let client = ResilientClient::new(ScriptedClient::new(vec![
+            Outcome::Unreachable,
+            Outcome::Failure("error"),
+            Outcome::Success,
+        ]))
+        .with_retry_policy(retry_policy(3));
+
+        let response = client.send(&event()).unwrap();
+
+        assert!(response.is_success());
+        let received_ids = client.client.received_ids.borrow();
+        assert_eq!(3, received_ids.len());
+        assert!(received_ids.iter().all(|id| *id == event().id));
+    }
+
+    #[test]
+    fn test_does_not_retry_non_retryable_errors() {
+        let client = ResilientClient::new(ScriptedClient::new(vec![
+            Outcome::Failure("badRequest"),
+            Outcome::Success,
+        ]))
+        .with_retry_policy(retry_policy(3));
+
+        let response = client.send(&event()).unwrap();
+
+        assert_eq!(Some("badRequest"), response.error_type());
+        assert_eq!(1, client.client.received_ids.borrow().len());
+    }
+
+    #[test]
+    fn test_opens_circuit_after_consecutive_failures() {
+        let client = ResilientClient::new(ScriptedClient::new(vec![
+            Outcome::Unreachable,
+            Outcome::Unreachable,
+            Outcome::Success,
+        ]))
+        .with_retry_policy(RetryPolicy::no_retry())
+        .with_circuit_breaker(CircuitBreakerPolicy {
+            failure_threshold: 2,
+            open_duration: Duration::from_secs(60),
+            scope: BreakerScope::Event,
+        });
+
+        assert!(client.send(&event()).is_err());
+        assert_eq!(BreakerState::Closed, client.breaker_state("event:test", 1));
+        assert!(client.send(&event()).is_err());
+        assert_eq!(BreakerState::Open, client.breaker_state("event:test", 1));
+
+        match client.send(&event()) {
+            Err(ClientError::CircuitOpen(key)) => {
+                assert_eq!("http://localhost/events event:test v1", key)
+            }
+            other => panic!("Expected open circuit, got: {:?}", other),
+        }
+        assert_eq!(2, client.client.received_ids.borrow().len());
+
+        let statuses = client.breaker_states();
+        assert_eq!(1, statuses.len());
+        assert_eq!(2, statuses[0].consecutive_failures);
+    }
+
+    #[test]
+    fn test_closes_circuit_after_successful_probe() {
+        let client = ResilientClient::new(ScriptedClient::new(vec![
+            Outcome::Failure("error"),
+            Outcome::Success,
+        ]))
+        .with_retry_policy(RetryPolicy::no_retry())
+        .with_circuit_breaker(CircuitBreakerPolicy {
+            failure_threshold: 1,
+            open_duration: Duration::from_millis(0),
+            scope: BreakerScope::Endpoint,
+        });
+
+        assert!(client.send(&event()).unwrap().is_error());
+        assert_eq!(BreakerState::Open, client.breaker_state("event:other", 1));
+
+        assert!(client.send(&event()).unwrap().is_success());
+        assert_eq!(BreakerState::Closed, client.breaker_state("event:test", 1));
+    }
+
+    #[test]
+    fn test_allows_one_probe_while_half_open() {
+        let policy = CircuitBreakerPolicy {
+            failure_threshold: 1,
+            open_duration: Duration::from_secs(10),
+            scope: BreakerScope::Event,
+        };
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new();
+        breaker.on_failure(&policy, now);
+        breaker.state = BreakerState::HalfOpen;
+
+        assert!(breaker.try_acquire(&policy, now));
+        assert!(!breaker.try_acquire(&policy, now + Duration::from_secs(1)));
+        assert!(breaker.try_acquire(&policy, now + Duration::from_secs(10)));
+
+        breaker.on_success();
+        assert_eq!(BreakerState::Closed, breaker.state);
+        assert!(breaker.try_acquire(&policy, now + Duration::from_secs(11)));
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::client::{ClientError, EventClient};
+use crate::events::{RequestEvent, ResponseEvent};
+use crate::transport::framed::{
+    read_frame, write_frame, Connection, Frame, DEFAULT_MAX_FRAME_BYTES,
+};
+
+type Pending = Arc<Mutex<Option<HashMap<Uuid, Sender<Result<ResponseEvent, ClientError>>>>>>;
+
+pub struct FramedEventClient<C: Connection> {
+    endpoint: String,
+    writer: Mutex<C>,
+    control: C,
+    pending: Pending,
+    max_frame_bytes: Arc<AtomicUsize>,
+    timeout: Duration,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl FramedEventClient<TcpStream> {
+    pub fn connect_tcp<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let endpoint = format!("tcp://{}", stream.peer_addr()?);
+        FramedEventClient::new(&endpoint, stream)
+    }
+}
+
+#[cfg(unix)]
+impl FramedEventClient<UnixStream> {
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let stream = UnixStream::connect(&path)?;
+        let endpoint = format!("unix://{}", path.as_ref().display());
+        FramedEventClient::new(&endpoint, stream)
+    }
+}
+
+impl<C: Connection> FramedEventClient<C> {
+    pub fn new(endpoint: &str, stream: C) -> io::Result<Self> {
+        let pending: Pending = Arc::new(Mutex::new(Some(HashMap::new())));
+        let control = stream.try_clone()?;
+        let reader = stream.try_clone()?;
+        let max_frame_bytes = Arc::new(AtomicUsize::new(DEFAULT_MAX_FRAME_BYTES));
+        let reader = {
+            let (pending, max_frame_bytes) = (pending.clone(), max_frame_bytes.clone());
+            let endpoint = String::from(endpoint);
+            thread::spawn(move || read_responses(&endpoint, reader, pending, &max_frame_bytes))
+        };
+
+        Ok(FramedEventClient {
+            endpoint: String::from(endpoint),
+            writer: Mutex::new(stream),
+            control,
+            pending,
+            max_frame_bytes,
+            timeout: Duration::from_secs(30),
+            reader: Some(reader),
+        })
+    }
+
+    pub fn with_max_frame_bytes(self, max_frame_bytes: usize) -> Self {
+        self.max_frame_bytes
+            .store(max_frame_bytes, Ordering::SeqCst);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn forget(&self, id: &Uuid) {
+        if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+            pending.remove(id);
+        }
+    }
+}
+
+fn read_responses<C: Connection>(
+    endpoint: &str,
+    stream: C,
+    pending: Pending,
+    max_frame_bytes: &AtomicUsize,
+) {
+    let mut reader = BufReader::new(stream);
+    let mut rejection = None;
+    loop {
+        match read_frame(&mut reader, max_frame_bytes.load(Ordering::SeqCst)) {
+            Ok(Some(Frame::Payload(payload))) => {
+                let response: ResponseEvent = match serde_json::from_slice(&payload) {
+                    Ok(response) => response,
+                    Err(err) => {
+                        log::warn!("Invalid response event from {}: {}", endpoint, err);
+                        continue;
+                    }
+                };
+                let mut pending = pending.lock().unwrap();
+                let pending = match pending.as_mut() {
+                    Some(pending) => pending,
+                    None => break,
+                };
+                match pending.remove(&response.id) {
+                    Some(sender) => {
+                        let _ = sender.send(Ok(response));
+                    }
+                    None if response.is_error() => {
+                        let error = response.error().unwrap();
+                        log::warn!(
+                            "{} answered {} ({}) without a matching request",
+                            endpoint,
+                            error.code(),
+                            response.name
+                        );
+                        rejection = Some(format!("{} {}", error.code(), error.parameters()));
+                    }
+                    None => log::warn!(
+                        "Discarding response {} ({}) from {}: no request is waiting for it",
+                        response.id,
+                        response.name,
+                        endpoint
+                    ),
+                }
+            }
+            Ok(Some(Frame::TooLarge(length))) => log::warn!(
+                "Discarding response frame of {} bytes from {}: exceeds the size limit",
+                length,
+                endpoint
+            ),
+            Ok(None) => break,
+            Err(err) => {
+                log::warn!("Connection to {} failed: {}", endpoint, err);
+                break;
+            }
+        }
+    }
+    let pending = pending.lock().unwrap().take();
+    if let Some(rejection) = rejection {
+        for (_, sender) in pending.into_iter().flatten() {
+            let _ = sender.send(Err(ClientError::Transport(format!(
+                "connection to {} closed after it rejected a request with {}",
+                endpoint, rejection
+            ))));
+        }
+    }
+}
+
+impl<C: Connection> EventClient for FramedEventClient<C> {
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+        let closed = || ClientError::Transport(format!("connection to {} closed", self.endpoint));
+
+        let payload =
+            serde_json::to_vec(event).map_err(|err| ClientError::Transport(format!("{}", err)))?;
+        let max_frame_bytes = self.max_frame_bytes.load(Ordering::SeqCst);
+        if payload.len() > max_frame_bytes {
+            return Err(ClientError::Transport(format!(
+                "frame of {} bytes exceeds the {} byte limit",
+                payload.len(),
+                max_frame_bytes
+            )));
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(pending) if pending.contains_key(&event.id) => {
+                return Err(ClientError::Transport(format!(
+                    "request {} is already in flight",
+                    event.id
+                )))
+            }
+            Some(pending) => {
+                pending.insert(event.id, sender);
+            }
+            None => return Err(closed()),
+        }
+
+        if let Err(err) = write_frame(&mut *self.writer.lock().unwrap(), &payload) {
+            self.forget(&event.id);
+            return Err(ClientError::Transport(format!("{}", err)));
+        }
+
+        match receiver.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                self.forget(&event.id);
+                Err(ClientError::Transport(format!(
+                    "timed out waiting for response {}",
+                    event.id
+                )))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(closed()),
+        }
+    }
+}
+
+impl<C: Connection> Drop for FramedEventClient<C> {
+    fn drop(&mut self) {
+        let _ = self.control.shutdown(Shutdown::Both);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::client::framed::FramedEventClient;
+    use crate::client::{ClientError, EventClient};
+    use crate::events::{parse_event, response_for, RequestEvent};
+    use crate::transport::framed::{read_frame, write_frame, Frame};
+
+    #[test]
+    fn test_fails_pending_requests_when_connection_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            drop(stream);
+        });
+
+        let client = FramedEventClient::connect_tcp(address)
+            .unwrap()
+            .with_max_frame_bytes(512);
+        let event = parse_event(
+            r#"{
+                "name": "event:test",
+                "version": 1,
+                "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                "payload": {},
+                "metadata": {},
+                "identity": {},
+                "auth": {}
+            }"#,
+        )
+        .unwrap();
+        server.join().unwrap();
+
+        assert!(matches!(
+            client.send(&event),
+            Err(ClientError::Transport(_))
+        ));
+
+        let mut large = event.clone();
+        large.payload = serde_json::json!("x".repeat(1024));
+        match client.send(&large) {
+            Err(ClientError::Transport(message)) => assert!(message.contains("512 byte limit")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ignores_malformed_error_frames_without_a_matching_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = match read_frame(&mut stream, 4096).unwrap() {
+                Some(Frame::Payload(payload)) => payload,
+                other => panic!("unexpected frame: {:?}", other),
+            };
+            let request: RequestEvent = serde_json::from_slice(&request).unwrap();
+            let malformed = serde_json::json!({
+                "name": "event:test:badRequest",
+                "version": 1,
+                "id": "00000000-0000-0000-0000-000000000000",
+                "flowId": request.flow_id,
+                "payload": {},
+                "metadata": {},
+                "identity": {},
+                "auth": {}
+            });
+            write_frame(&mut stream, malformed.to_string().as_bytes()).unwrap();
+            let response = serde_json::to_vec(&response_for(&request, "ok")).unwrap();
+            write_frame(&mut stream, &response).unwrap();
+        });
+
+        let client = FramedEventClient::connect_tcp(address).unwrap();
+        let event = parse_event(
+            r#"{
+                "name": "event:test",
+                "version": 1,
+                "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                "payload": {},
+                "metadata": {},
+                "identity": {},
+                "auth": {}
+            }"#,
+        )
+        .unwrap();
+
+        let response = client.send(&event).unwrap();
+        server.join().unwrap();
+        assert_eq!("ok", response.payload);
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use crate::client::{ClientError, EventClient};
+use crate::events::{RequestEvent, ResponseEvent};
+
+pub struct HttpEventClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpEventClient {
+    pub fn new(url: &str) -> Self {
+        HttpEventClient::with_timeout(url, Duration::from_secs(30))
+    }
+
+    pub fn with_timeout(url: &str, timeout: Duration) -> Self {
+        HttpEventClient {
+            url: String::from(url),
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+}
+
+impl EventClient for HttpEventClient {
+    fn endpoint(&self) -> &str {
+        &self.url
+    }
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+        let body = serde_json::to_string(event)
+            .map_err(|err| ClientError::Transport(format!("{}", err)))?;
+
+        let response = match self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(ClientError::Transport(format!("{}", err))),
+        };
+
+        let status = response.status();
+        let body = response
+            .into_string()
+            .map_err(|err| ClientError::Transport(format!("{}", err)))?;
+        serde_json::from_str(&body).map_err(|err| {
+            ClientError::Transport(format!("invalid response event (HTTP {}): {}", status, err))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::client::http::HttpEventClient;
+    use crate::client::{ClientError, EventClient};
+    use crate::events::{parse_event, response_for};
+
+    fn serve_once(status: &'static str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request = vec![0; content_length];
+            reader.read_exact(&mut request).unwrap();
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        format!("http://{}/events/", address)
+    }
+
+    #[test]
+    fn test_sends_event_over_http() {
+        let event = parse_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+        let body = serde_json::to_string(&response_for(&event, "ok")).unwrap();
+
+        let client = HttpEventClient::new(&serve_once("200 OK", body));
+        let response = client.send(&event).unwrap();
+
+        assert!(response.is_success());
+        assert_eq!(event.id, response.id);
+    }
+
+    #[test]
+    fn test_reports_invalid_responses_as_transport_errors() {
+        let event = parse_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+
+        let client = HttpEventClient::new(&serve_once(
+            "502 Bad Gateway",
+            String::from("upstream down"),
+        ));
+
+        match client.send(&event) {
+            Err(ClientError::Transport(message)) => assert!(message.contains("HTTP 502")),
+            other => panic!("Expected transport error, got: {:?}", other),
+        }
+    }
+}
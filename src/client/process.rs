@@ -0,0 +1,100 @@
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+use crate::client::{ClientError, EventClient};
+use crate::events::{RequestEvent, ResponseEvent};
+
+pub struct ProcessEventClient {
+    command: String,
+    child: Child,
+    pipes: Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+}
+
+impl ProcessEventClient {
+    pub fn spawn(program: &str, args: &[&str]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        let mut command = vec![program];
+        command.extend_from_slice(args);
+        Ok(ProcessEventClient {
+            command: command.join(" "),
+            child,
+            pipes: Mutex::new((stdin, stdout)),
+        })
+    }
+}
+
+impl EventClient for ProcessEventClient {
+    fn endpoint(&self) -> &str {
+        &self.command
+    }
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+        let transport_error = |err: io::Error| ClientError::Transport(format!("{}", err));
+
+        let mut line = serde_json::to_string(event)
+            .map_err(|err| ClientError::Transport(format!("{}", err)))?;
+        line.push('\n');
+
+        let mut pipes = self.pipes.lock().unwrap();
+        let (stdin, stdout) = &mut *pipes;
+        stdin.write_all(line.as_bytes()).map_err(transport_error)?;
+        stdin.flush().map_err(transport_error)?;
+
+        let mut response = String::new();
+        if stdout.read_line(&mut response).map_err(transport_error)? == 0 {
+            return Err(ClientError::Transport(format!(
+                "process '{}' closed its output",
+                self.command
+            )));
+        }
+        serde_json::from_str(&response)
+            .map_err(|err| ClientError::Transport(format!("invalid response event: {}", err)))
+    }
+}
+
+impl Drop for ProcessEventClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::client::process::ProcessEventClient;
+    use crate::client::EventClient;
+    use crate::events::parse_event;
+
+    #[test]
+    fn test_exchanges_events_over_process_pipes() {
+        let client = ProcessEventClient::spawn("cat", &[]).unwrap();
+        let event = parse_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {"ping": true},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+
+        let response = client.send(&event).unwrap();
+
+        assert_eq!("cat", client.endpoint());
+        assert_eq!(event.id, response.id);
+        assert_eq!(true, response.payload["ping"]);
+    }
+}
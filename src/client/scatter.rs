@@ -0,0 +1,328 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::client::{ClientError, EventClient};
+use crate::errors::EventErrorType;
+use crate::events::{RequestEvent, ResponseEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GatherMode {
+    FailFast,
+    BestEffort,
+}
+
+#[derive(Debug)]
+pub enum GatherOutcome {
+    Success(ResponseEvent),
+    Failed(EventErrorType),
+    // The call was sent but no response arrived before the gather stopped waiting.
+    // It keeps running in the background, so its side effects may still happen.
+    Abandoned,
+    // The call was never sent.
+    Skipped,
+}
+
+impl GatherOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, GatherOutcome::Success(_))
+    }
+}
+
+#[derive(Debug)]
+pub struct GatherResult {
+    pub outcomes: Vec<GatherOutcome>,
+}
+
+impl GatherResult {
+    pub fn is_success(&self) -> bool {
+        self.outcomes.iter().all(GatherOutcome::is_success)
+    }
+
+    pub fn responses(&self) -> Vec<Option<&ResponseEvent>> {
+        self.outcomes
+            .iter()
+            .map(|outcome| match outcome {
+                GatherOutcome::Success(response) => Some(response),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+pub struct ScatterGather<C: EventClient + Send + Sync + 'static> {
+    client: Arc<C>,
+    deadline: Duration,
+    mode: GatherMode,
+    max_concurrency: usize,
+}
+
+impl<C: EventClient + Send + Sync + 'static> ScatterGather<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        ScatterGather {
+            client,
+            deadline: Duration::from_secs(30),
+            mode: GatherMode::BestEffort,
+            max_concurrency: 8,
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: GatherMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn send(&self, events: Vec<RequestEvent>) -> GatherResult {
+        let deadline = Instant::now() + self.deadline;
+        let mut outcomes: Vec<Option<GatherOutcome>> = events.iter().map(|_| None).collect();
+        let flow_id = events.first().map(|event| event.flow_id);
+
+        let mut queue = VecDeque::new();
+        for (idx, event) in events.into_iter().enumerate() {
+            if Some(event.flow_id) != flow_id {
+                outcomes[idx] = Some(GatherOutcome::Failed(EventErrorType::bad_request(
+                    "FLOW_ID_MISMATCH",
+                    json!({ "expected": flow_id, "actual": event.flow_id }),
+                )));
+                continue;
+            }
+            queue.push_back((idx, event));
+        }
+        let mut waiting = queue.len();
+        let workers = self.max_concurrency.min(waiting);
+        let queue = Arc::new(Mutex::new(queue));
+
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let (client, sender, queue) = (self.client.clone(), sender.clone(), queue.clone());
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().pop_front();
+                let (idx, event) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let result = client.send(&event);
+                if sender
+                    .send((idx, outcome_of(client.endpoint(), result)))
+                    .is_err()
+                {
+                    break;
+                }
+            });
+        }
+
+        let failed = outcomes.iter().any(|outcome| outcome.is_some());
+        if !(failed && self.mode == GatherMode::FailFast) {
+            while waiting > 0 {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                match receiver.recv_timeout(deadline - now) {
+                    Ok((idx, outcome)) => {
+                        waiting -= 1;
+                        let failed = !outcome.is_success();
+                        outcomes[idx] = Some(outcome);
+                        if failed && self.mode == GatherMode::FailFast {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+
+        for (idx, _) in queue.lock().unwrap().drain(..) {
+            outcomes[idx] = Some(GatherOutcome::Skipped);
+        }
+        GatherResult {
+            outcomes: outcomes
+                .into_iter()
+                .map(|outcome| outcome.unwrap_or(GatherOutcome::Abandoned))
+                .collect(),
+        }
+    }
+}
+
+fn outcome_of(endpoint: &str, result: Result<ResponseEvent, ClientError>) -> GatherOutcome {
+    match result {
+        Ok(response) => match response.error() {
+            Some(error) => GatherOutcome::Failed(error),
+            None => GatherOutcome::Success(response),
+        },
+        Err(err) => GatherOutcome::Failed(
+            EventErrorType::generic(
+                "UNAVAILABLE",
+                json!({ "endpoint": endpoint, "message": format!("{}", err) }),
+            )
+            .with_source(err),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::client::scatter::{GatherMode, GatherOutcome, ScatterGather};
+    use crate::client::{ClientError, EventClient};
+    use crate::errors::{error_for, EventErrorType};
+    use crate::events::{child_event, new_event, response_for, RequestEvent, ResponseEvent};
+
+    struct StubClient {
+        gate: Mutex<Receiver<()>>,
+        active: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl StubClient {
+        fn new() -> (Arc<StubClient>, Sender<()>) {
+            let (release, gate) = mpsc::channel();
+            let client = StubClient {
+                gate: Mutex::new(gate),
+                active: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            };
+            (Arc::new(client), release)
+        }
+    }
+
+    impl EventClient for StubClient {
+        fn endpoint(&self) -> &str {
+            "stub"
+        }
+
+        fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            if event.payload == "block" {
+                let _ = self.gate.lock().unwrap().recv();
+            }
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            if event.payload == "malformed" {
+                let mut response = response_for(event, "boom");
+                response.name = String::from("price:get:error");
+                return Ok(response);
+            }
+            if event.payload == "fail" {
+                let err = EventErrorType::not_found("OUT_OF_STOCK", json!({}));
+                return Ok(error_for(event, &err));
+            }
+            Ok(response_for(event, json!({ "price": 10 })))
+        }
+    }
+
+    fn events(calls: &[&str]) -> Vec<RequestEvent> {
+        let parent = new_event("product:get", 1, json!({}));
+        calls
+            .iter()
+            .map(|call| child_event(&parent, "price:get", 1, call))
+            .collect()
+    }
+
+    #[test]
+    fn test_best_effort_collects_every_outcome() {
+        let (client, release) = StubClient::new();
+        let gather = ScatterGather::new(client).with_deadline(Duration::from_millis(50));
+
+        let result = gather.send(events(&["ok", "fail", "block", "ok"]));
+        drop(release);
+
+        assert!(!result.is_success());
+        match &result.outcomes[..] {
+            [GatherOutcome::Success(first), GatherOutcome::Failed(err), GatherOutcome::Abandoned, GatherOutcome::Success(_)] =>
+            {
+                assert_eq!(json!({ "price": 10 }), first.payload);
+                assert_eq!("OUT_OF_STOCK", err.code());
+            }
+            outcomes => panic!("unexpected outcomes: {:?}", outcomes),
+        }
+        assert_eq!(2, result.responses().iter().flatten().count());
+    }
+
+    #[test]
+    fn test_reports_malformed_error_responses_as_failures() {
+        let (client, _release) = StubClient::new();
+        let gather = ScatterGather::new(client);
+
+        let result = gather.send(events(&["malformed"]));
+
+        match &result.outcomes[..] {
+            [GatherOutcome::Failed(err)] => {
+                assert_eq!("MALFORMED_ERROR_RESPONSE", err.code());
+                assert_eq!(json!({ "payload": "boom" }), *err.parameters());
+            }
+            outcomes => panic!("unexpected outcomes: {:?}", outcomes),
+        }
+    }
+
+    #[test]
+    fn test_fail_fast_abandons_pending_calls() {
+        let (client, release) = StubClient::new();
+        let gather = ScatterGather::new(client).with_mode(GatherMode::FailFast);
+
+        let result = gather.send(events(&["block", "fail"]));
+        drop(release);
+
+        assert!(matches!(
+            &result.outcomes[..],
+            [GatherOutcome::Abandoned, GatherOutcome::Failed(_)]
+        ));
+    }
+
+    #[test]
+    fn test_limits_concurrent_calls() {
+        let (client, release) = StubClient::new();
+        let gather = ScatterGather::new(client.clone())
+            .with_deadline(Duration::from_millis(50))
+            .with_max_concurrency(2);
+
+        let result = gather.send(events(&["block", "block", "ok"]));
+        drop(release);
+
+        assert!(matches!(
+            &result.outcomes[..],
+            [
+                GatherOutcome::Abandoned,
+                GatherOutcome::Abandoned,
+                GatherOutcome::Skipped
+            ]
+        ));
+        assert_eq!(2, client.peak.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_rejects_events_from_other_flows() {
+        let (client, _release) = StubClient::new();
+        let gather = ScatterGather::new(client);
+        let mut events = events(&["ok", "ok"]);
+        events[1].flow_id = new_event("other", 1, ()).flow_id;
+
+        let result = gather.send(events);
+
+        assert!(result.outcomes[0].is_success());
+        match &result.outcomes[1] {
+            GatherOutcome::Failed(err) => assert_eq!("FLOW_ID_MISMATCH", err.code()),
+            outcome => panic!("unexpected outcome: {:?}", outcome),
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::events::ResponseEvent;
+use crate::processor::{EventProcessor, ProcessorStore};
+use crate::transport::ShutdownHandle;
+
+pub const REPLY_TO: &str = "replyTo";
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub tag: u64,
+    pub payload: String,
+    pub attempts: u32,
+}
+
+pub trait EventConsumer {
+    fn receive(&self, timeout: Duration) -> io::Result<Option<Delivery>>;
+
+    fn ack(&self, delivery: &Delivery) -> io::Result<()>;
+
+    fn nack(&self, delivery: &Delivery, requeue: bool) -> io::Result<()>;
+}
+
+pub trait ReplyPublisher {
+    fn publish(&self, destination: &str, response: &ResponseEvent) -> io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Ack,
+    Requeue,
+    Reject,
+}
+
+#[derive(Debug, Clone)]
+pub struct AckPolicy {
+    pub max_attempts: u32,
+    pub retryable_error_types: Vec<String>,
+}
+
+impl AckPolicy {
+    pub fn decide(&self, response: &ResponseEvent, attempts: u32) -> Decision {
+        match response.error_type() {
+            None => Decision::Ack,
+            Some(error_type) if self.is_retryable(error_type) => self.retry(attempts),
+            Some(_) => Decision::Ack,
+        }
+    }
+
+    pub fn retry(&self, attempts: u32) -> Decision {
+        if attempts < self.max_attempts {
+            Decision::Requeue
+        } else {
+            Decision::Reject
+        }
+    }
+
+    pub fn is_retryable(&self, error_type: &str) -> bool {
+        self.retryable_error_types
+            .iter()
+            .any(|retryable| retryable == error_type)
+    }
+}
+
+impl Default for AckPolicy {
+    fn default() -> Self {
+        AckPolicy {
+            max_attempts: 5,
+            retryable_error_types: vec![String::from("error"), String::from("tooManyRequests")],
+        }
+    }
+}
+
+pub struct ConsumerRunner {
+    consumer: Box<dyn EventConsumer>,
+    replies: Option<Box<dyn ReplyPublisher>>,
+    policy: AckPolicy,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ConsumerRunner {
+    pub fn new(consumer: Box<dyn EventConsumer>) -> Self {
+        ConsumerRunner {
+            consumer,
+            replies: None,
+            policy: AckPolicy::default(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_replies(mut self, replies: Box<dyn ReplyPublisher>) -> Self {
+        self.replies = Some(replies);
+        self
+    }
+
+    pub fn with_ack_policy(mut self, policy: AckPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(self.shutdown.clone())
+    }
+
+    pub fn run<S: ?Sized + ProcessorStore>(&self, processor: &EventProcessor<S>) -> io::Result<()> {
+        while !self.shutdown.load(Ordering::SeqCst) {
+            self.run_once(processor, POLL_INTERVAL)?;
+        }
+        Ok(())
+    }
+
+    pub fn run_once<S: ?Sized + ProcessorStore>(
+        &self,
+        processor: &EventProcessor<S>,
+        timeout: Duration,
+    ) -> io::Result<Option<Decision>> {
+        let delivery = match self.consumer.receive(timeout)? {
+            Some(delivery) => delivery,
+            None => return Ok(None),
+        };
+
+        let response = processor.process_event(&delivery.payload);
+        let mut decision = self.policy.decide(&response, delivery.attempts);
+        if decision != Decision::Requeue {
+            if let Err(err) = self.reply(&delivery, &response) {
+                log::warn!(
+                    "Could not publish reply for {} (delivery {}): {}",
+                    response.name,
+                    delivery.tag,
+                    err
+                );
+                decision = self.policy.retry(delivery.attempts);
+            }
+        }
+
+        match decision {
+            Decision::Ack => self.consumer.ack(&delivery)?,
+            Decision::Requeue => self.consumer.nack(&delivery, true)?,
+            Decision::Reject => self.consumer.nack(&delivery, false)?,
+        }
+        Ok(Some(decision))
+    }
+
+    fn reply(&self, delivery: &Delivery, response: &ResponseEvent) -> io::Result<()> {
+        let replies = match &self.replies {
+            Some(replies) => replies,
+            None => return Ok(()),
+        };
+        match reply_to(&delivery.payload) {
+            Some(destination) => replies.publish(&destination, response),
+            None => Ok(()),
+        }
+    }
+}
+
+fn reply_to(payload: &str) -> Option<String> {
+    let event: Value = serde_json::from_str(payload).ok()?;
+    event["metadata"][REPLY_TO].as_str().map(String::from)
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_tag: u64,
+    ready: VecDeque<Delivery>,
+    unacked: HashMap<u64, Delivery>,
+    rejected: Vec<Delivery>,
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryQueue {
+    state: Arc<(Mutex<QueueState>, Condvar)>,
+}
+
+impl InMemoryQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, payload: &str) {
+        let (state, available) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.next_tag += 1;
+        let delivery = Delivery {
+            tag: state.next_tag,
+            payload: String::from(payload),
+            attempts: 0,
+        };
+        state.ready.push_back(delivery);
+        available.notify_one();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.0.lock().unwrap().ready.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn unacked(&self) -> usize {
+        self.state.0.lock().unwrap().unacked.len()
+    }
+
+    pub fn rejected(&self) -> Vec<Delivery> {
+        self.state.0.lock().unwrap().rejected.clone()
+    }
+}
+
+impl EventConsumer for InMemoryQueue {
+    fn receive(&self, timeout: Duration) -> io::Result<Option<Delivery>> {
+        let deadline = Instant::now() + timeout;
+        let (state, available) = &*self.state;
+        let mut state = state.lock().unwrap();
+        loop {
+            if let Some(mut delivery) = state.ready.pop_front() {
+                delivery.attempts += 1;
+                state.unacked.insert(delivery.tag, delivery.clone());
+                return Ok(Some(delivery));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            state = available.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    fn ack(&self, delivery: &Delivery) -> io::Result<()> {
+        let mut state = self.state.0.lock().unwrap();
+        match state.unacked.remove(&delivery.tag) {
+            Some(_) => Ok(()),
+            None => Err(unknown_delivery(delivery)),
+        }
+    }
+
+    fn nack(&self, delivery: &Delivery, requeue: bool) -> io::Result<()> {
+        let (state, available) = &*self.state;
+        let mut state = state.lock().unwrap();
+        let delivery = state
+            .unacked
+            .remove(&delivery.tag)
+            .ok_or_else(|| unknown_delivery(delivery))?;
+        if requeue {
+            state.ready.push_back(delivery);
+            available.notify_one();
+        } else {
+            state.rejected.push(delivery);
+        }
+        Ok(())
+    }
+}
+
+fn unknown_delivery(delivery: &Delivery) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("delivery {} is not awaiting acknowledgement", delivery.tag),
+    )
+}
+
+#[derive(Default)]
+pub struct InMemoryBroker {
+    queues: Mutex<HashMap<String, InMemoryQueue>>,
+}
+
+impl InMemoryBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn queue(&self, name: &str) -> InMemoryQueue {
+        self.queues
+            .lock()
+            .unwrap()
+            .entry(String::from(name))
+            .or_default()
+            .clone()
+    }
+}
+
+impl ReplyPublisher for InMemoryBroker {
+    fn publish(&self, destination: &str, response: &ResponseEvent) -> io::Result<()> {
+        self.queue(destination)
+            .push(&serde_json::to_string(response)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::consumer::{
+        AckPolicy, ConsumerRunner, Decision, EventConsumer, InMemoryBroker, InMemoryQueue,
+        ReplyPublisher,
+    };
+    use crate::errors::EventErrorType;
+    use crate::events::{response_for, ResponseEvent};
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    fn event(name: &str) -> String {
+        json!({
+            "name": name,
+            "version": 1,
+            "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+            "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+            "payload": {},
+            "metadata": { "replyTo": "replies" },
+            "identity": {},
+            "auth": {}
+        })
+        .to_string()
+    }
+
+    fn processor() -> EventProcessor {
+        let mut store = SimpleEventStore::new();
+        store.add("event:ok", 1, |req| Ok(response_for(req, "ok")));
+        store.add("event:flaky", 1, |_| {
+            Err(EventErrorType::generic("UNAVAILABLE", json!({})))
+        });
+        store.add("event:invalid", 1, |_| {
+            Err(EventErrorType::bad_request("INVALID", json!({})))
+        });
+        EventProcessor::new(Box::new(store))
+    }
+
+    #[test]
+    fn test_acks_and_publishes_replies() {
+        let broker = InMemoryBroker::new();
+        let queue = broker.queue("events");
+        let replies = broker.queue("replies");
+        queue.push(&event("event:ok"));
+
+        let runner = ConsumerRunner::new(Box::new(queue.clone())).with_replies(Box::new(broker));
+
+        assert_eq!(
+            Some(Decision::Ack),
+            runner.run_once(&processor(), TIMEOUT).unwrap()
+        );
+        assert_eq!(0, queue.unacked());
+        let reply = replies.receive(TIMEOUT).unwrap().unwrap();
+        let response: ResponseEvent = serde_json::from_str(&reply.payload).unwrap();
+        assert_eq!("event:ok:response", response.name);
+        assert_eq!(None, runner.run_once(&processor(), TIMEOUT).unwrap());
+    }
+
+    #[test]
+    fn test_requeues_retryable_errors_until_max_attempts() {
+        let queue = InMemoryQueue::new();
+        queue.push(&event("event:flaky"));
+        let runner = ConsumerRunner::new(Box::new(queue.clone())).with_ack_policy(AckPolicy {
+            max_attempts: 2,
+            ..Default::default()
+        });
+
+        let processor = processor();
+        assert_eq!(
+            Some(Decision::Requeue),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert_eq!(
+            Some(Decision::Reject),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert!(queue.is_empty());
+        assert_eq!(2, queue.rejected()[0].attempts);
+    }
+
+    #[test]
+    fn test_acks_non_retryable_errors() {
+        let queue = InMemoryQueue::new();
+        queue.push(&event("event:invalid"));
+        queue.push("not json");
+        let runner = ConsumerRunner::new(Box::new(queue.clone()));
+
+        let processor = processor();
+        assert_eq!(
+            Some(Decision::Ack),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert_eq!(
+            Some(Decision::Ack),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert!(queue.is_empty());
+        assert!(queue.rejected().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_after_max_attempts_when_replies_cannot_be_published() {
+        struct BrokenPublisher;
+
+        impl ReplyPublisher for BrokenPublisher {
+            fn publish(&self, _destination: &str, _response: &ResponseEvent) -> io::Result<()> {
+                Err(io::Error::other("reply queue unavailable"))
+            }
+        }
+
+        let queue = InMemoryQueue::new();
+        queue.push(&event("event:ok"));
+        let runner = ConsumerRunner::new(Box::new(queue.clone()))
+            .with_replies(Box::new(BrokenPublisher))
+            .with_ack_policy(AckPolicy {
+                max_attempts: 2,
+                ..Default::default()
+            });
+
+        let processor = processor();
+        assert_eq!(
+            Some(Decision::Requeue),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert_eq!(
+            Some(Decision::Reject),
+            runner.run_once(&processor, TIMEOUT).unwrap()
+        );
+        assert_eq!(None, runner.run_once(&processor, TIMEOUT).unwrap());
+        assert_eq!(2, queue.rejected()[0].attempts);
+    }
+}
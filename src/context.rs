@@ -0,0 +1,224 @@
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::errors::{error_for, EventErrorType};
+use crate::events::{child_event, epoch_millis, RequestEvent, ResponseEvent};
+use crate::processor::Dispatch;
+
+pub const CALL_DEPTH: &str = "callDepth";
+pub const DEADLINE: &str = "deadline";
+
+pub struct EventContext<'a> {
+    processor: Option<&'a dyn Dispatch>,
+    event: &'a RequestEvent,
+    depth: u32,
+    deadline: Option<u64>,
+}
+
+impl<'a> EventContext<'a> {
+    pub(crate) fn new(processor: &'a dyn Dispatch, event: &'a RequestEvent) -> Self {
+        EventContext {
+            processor: Some(processor),
+            ..EventContext::detached(event)
+        }
+    }
+
+    pub fn detached(event: &'a RequestEvent) -> Self {
+        EventContext {
+            processor: None,
+            event,
+            depth: event.metadata[CALL_DEPTH].as_u64().unwrap_or(0) as u32,
+            deadline: event.metadata[DEADLINE].as_u64(),
+        }
+    }
+
+    pub fn event(&self) -> &RequestEvent {
+        self.event
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn deadline(&self) -> Option<u64> {
+        self.deadline
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| Duration::from_millis(deadline.saturating_sub(epoch_millis())))
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        let deadline = epoch_millis() + timeout.as_millis() as u64;
+        self.deadline = Some(
+            self.deadline
+                .map_or(deadline, |current| current.min(deadline)),
+        );
+        self
+    }
+
+    pub fn sub_event<T: Serialize>(&self, name: &str, version: u16, payload: T) -> RequestEvent {
+        let mut event = child_event(self.event, name, version, payload);
+        event.metadata[CALL_DEPTH] = json!(self.depth + 1);
+        if let Some(deadline) = self.deadline {
+            event.metadata[DEADLINE] = json!(deadline);
+        }
+        event
+    }
+
+    pub fn dispatch<T: Serialize>(&self, name: &str, version: u16, payload: T) -> ResponseEvent {
+        let event = self.sub_event(name, version, payload);
+        match self.processor {
+            Some(processor) => processor.dispatch(&event),
+            None => error_for(
+                &event,
+                &EventErrorType::generic(
+                    "DISPATCH_UNAVAILABLE",
+                    json!({ "event": event.name, "version": event.version }),
+                ),
+            ),
+        }
+    }
+
+    pub fn call<Req, Resp>(
+        &self,
+        name: &str,
+        version: u16,
+        payload: Req,
+    ) -> Result<Resp, EventErrorType>
+    where
+        Req: Serialize,
+        Resp: DeserializeOwned,
+    {
+        let response = self.dispatch(name, version, payload);
+        if response.is_error() {
+            return Err(response.get_error());
+        }
+        Resp::deserialize(&response.payload).map_err(|err| {
+            EventErrorType::generic(
+                "INVALID_SUB_EVENT_RESPONSE",
+                json!({ "event": name, "version": version, "message": format!("{}", err) }),
+            )
+            .with_source(err)
+        })
+    }
+}
+
+pub(crate) fn call_depth_exceeded(max_depth: u32) -> EventErrorType {
+    EventErrorType::generic("CALL_DEPTH_EXCEEDED", json!({ "maxDepth": max_depth }))
+}
+
+pub(crate) fn deadline_exceeded(deadline: u64) -> EventErrorType {
+    EventErrorType::expired("DEADLINE_EXCEEDED", json!({ "deadline": deadline }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use crate::context::EventContext;
+    use crate::errors::EventErrorType;
+    use crate::events::{epoch_millis, new_event, response_for};
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+    use events_protocol_macros::event_handler;
+
+    #[event_handler(name = "profile:owner", version = 1)]
+    fn profile_owner(
+        #[context] context: &EventContext,
+        payload: Value,
+    ) -> Result<Value, EventErrorType> {
+        let account: Value = context.call("account:get", 1, payload)?;
+        Ok(account["user"].clone())
+    }
+
+    fn processor() -> EventProcessor {
+        let mut store = SimpleEventStore::new();
+        store.add("account:get", 1, |req| {
+            Ok(response_for(
+                req,
+                json!({ "flowId": req.flow_id, "user": req.identity["user"] }),
+            ))
+        });
+        store.add_with_context("profile:get", 1, |context, req| {
+            let account: Value = context.call("account:get", 1, json!({}))?;
+            Ok(response_for(
+                req,
+                json!({ "account": account, "depth": context.depth() }),
+            ))
+        });
+        store.add_with_context("loop", 1, |context, req| {
+            let response = context.dispatch("loop", 1, json!({}));
+            Ok(response_for(req, response.payload))
+        });
+        store.register(ProfileOwnerHandler);
+        EventProcessor::new(Box::new(store)).with_max_call_depth(3)
+    }
+
+    #[test]
+    fn test_sub_events_inherit_flow_and_identity() {
+        let mut event = new_event("profile:get", 1, json!({}));
+        event.identity = json!({ "user": "alice" });
+
+        let response = processor().process_event(&serde_json::to_string(&event).unwrap());
+
+        assert_eq!(
+            json!({
+                "account": { "flowId": event.flow_id, "user": "alice" },
+                "depth": 0
+            }),
+            response.payload
+        );
+    }
+
+    #[test]
+    fn test_annotated_handlers_receive_context() {
+        let mut event = new_event("profile:owner", 1, json!({}));
+        event.identity = json!({ "user": "bob" });
+
+        let response = processor().process_event(&serde_json::to_string(&event).unwrap());
+
+        assert_eq!(json!("bob"), response.payload);
+    }
+
+    #[test]
+    fn test_stops_recursive_dispatch() {
+        let event = new_event("loop", 1, json!({}));
+
+        let response = processor().process_event(&serde_json::to_string(&event).unwrap());
+
+        assert_eq!(
+            json!({
+                "code": "CALL_DEPTH_EXCEEDED",
+                "parameters": { "maxDepth": 3 }
+            }),
+            response.payload
+        );
+    }
+
+    #[test]
+    fn test_propagates_and_enforces_deadlines() {
+        let event = new_event("account:get", 1, json!({}));
+        let context = EventContext::detached(&event).with_timeout(Duration::from_secs(5));
+
+        let sub_event = context.sub_event("account:get", 1, json!({}));
+        assert_eq!(1, sub_event.metadata["callDepth"]);
+        assert!(sub_event.metadata["deadline"].as_u64().unwrap() > epoch_millis());
+        assert_eq!(
+            "DISPATCH_UNAVAILABLE",
+            context.dispatch("account:get", 1, ()).get_error().code()
+        );
+
+        let mut expired = event.clone();
+        expired.metadata = json!({ "deadline": epoch_millis() - 1 });
+        let response = processor().process_event(&serde_json::to_string(&expired).unwrap());
+        assert_eq!("account:get:expired", response.name);
+        assert_eq!("DEADLINE_EXCEEDED", response.get_error().code());
+    }
+}
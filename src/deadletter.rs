@@ -0,0 +1,163 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::events::{RequestEvent, ResponseEvent};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter<'a> {
+    pub timestamp: u64,
+    pub payload: &'a str,
+    pub event: Option<&'a RequestEvent>,
+    pub response: &'a ResponseEvent,
+}
+
+pub trait DeadLetterSink: Send + Sync {
+    fn dead_letter(&self, letter: &DeadLetter) -> io::Result<()>;
+}
+
+pub struct DeadLetterFilter {
+    error_types: Option<Vec<String>>,
+}
+
+impl DeadLetterFilter {
+    pub fn only(error_types: &[&str]) -> Self {
+        DeadLetterFilter {
+            error_types: Some(error_types.iter().map(|t| String::from(*t)).collect()),
+        }
+    }
+
+    pub fn all_errors() -> Self {
+        DeadLetterFilter { error_types: None }
+    }
+
+    pub fn matches(&self, response: &ResponseEvent) -> bool {
+        match (response.error_type(), &self.error_types) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(error_type), Some(error_types)) => error_types.iter().any(|t| t == error_type),
+        }
+    }
+}
+
+impl Default for DeadLetterFilter {
+    fn default() -> Self {
+        DeadLetterFilter::only(&["badProtocol", "eventNotFound", "error"])
+    }
+}
+
+pub struct JsonLinesDeadLetterSink {
+    file: Mutex<File>,
+}
+
+impl JsonLinesDeadLetterSink {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesDeadLetterSink {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl DeadLetterSink for JsonLinesDeadLetterSink {
+    fn dead_letter(&self, letter: &DeadLetter) -> io::Result<()> {
+        let mut line = serde_json::to_vec(letter)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    use crate::deadletter::{DeadLetterFilter, JsonLinesDeadLetterSink};
+    use crate::errors::EventError;
+    use crate::errors::EventErrorType::BadRequest;
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+
+    #[test]
+    fn test_writes_dead_letters_as_json_lines() {
+        let path = std::env::temp_dir().join(format!("dead-letters-{}.jsonl", Uuid::new_v4()));
+
+        let mut store = SimpleEventStore::new();
+        store.add("event:test", 1, |_req| {
+            Err(BadRequest(EventError::new("SOME_ERROR", Value::Null)))
+        });
+        let event_processor = EventProcessor::new(Box::new(store)).with_dead_letters(
+            Box::new(JsonLinesDeadLetterSink::open(&path).unwrap()),
+            DeadLetterFilter::default(),
+        );
+
+        event_processor.process_event("not an event");
+        event_processor.process_event(
+            r#"{
+                    "name": "event:unknown",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        );
+        event_processor.process_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        );
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let letters: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(2, letters.len());
+        assert_eq!("not an event", letters[0]["payload"]);
+        assert!(letters[0]["event"].is_null());
+        assert_eq!("badProtocol", letters[0]["response"]["name"]);
+        assert_eq!("event:unknown", letters[1]["event"]["name"]);
+        assert_eq!("eventNotFound", letters[1]["response"]["name"]);
+    }
+
+    #[test]
+    fn test_filters_dead_letters_by_error_type() {
+        let path = std::env::temp_dir().join(format!("dead-letters-{}.jsonl", Uuid::new_v4()));
+
+        let store = SimpleEventStore::new();
+        let event_processor = EventProcessor::new(Box::new(store)).with_dead_letters(
+            Box::new(JsonLinesDeadLetterSink::open(&path).unwrap()),
+            DeadLetterFilter::only(&["eventNotFound"]),
+        );
+
+        event_processor.process_event("not an event");
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(content.is_empty());
+    }
+}
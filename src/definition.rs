@@ -0,0 +1,213 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::errors::EventErrorType;
+use crate::schema::{InvalidSchema, PayloadSchema};
+use crate::store::EventStore;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorDeclaration {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+impl ErrorDeclaration {
+    pub fn new(error_type: &str, code: &str) -> Self {
+        ErrorDeclaration {
+            error_type: String::from(error_type),
+            code: String::from(code),
+            description: None,
+            parameters: None,
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(String::from(description));
+        self
+    }
+
+    pub fn parameters(mut self, schema: Value) -> Self {
+        self.parameters = Some(schema);
+        self
+    }
+
+    pub fn matches(&self, error: &EventErrorType) -> bool {
+        self.error_type == error.error_type() && self.code == error.code()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogEnforcement {
+    Off,
+    Warn,
+    Panic,
+}
+
+impl Default for CatalogEnforcement {
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            CatalogEnforcement::Warn
+        } else {
+            CatalogEnforcement::Off
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventDefinition {
+    pub name: String,
+    pub version: u16,
+    pub description: Option<String>,
+    pub deprecated: bool,
+    pub auditable: bool,
+    pub errors: Vec<ErrorDeclaration>,
+    pub request_schema: Option<PayloadSchema>,
+    pub response_schema: Option<PayloadSchema>,
+}
+
+impl EventDefinition {
+    pub fn new(name: &str, version: u16) -> Self {
+        EventDefinition {
+            name: String::from(name),
+            version,
+            description: None,
+            deprecated: false,
+            auditable: false,
+            errors: vec![],
+            request_schema: None,
+            response_schema: None,
+        }
+    }
+
+    pub fn description(&mut self, description: &str) -> &mut Self {
+        self.description = Some(String::from(description));
+        self
+    }
+
+    pub fn deprecated(&mut self) -> &mut Self {
+        self.deprecated = true;
+        self
+    }
+
+    pub fn auditable(&mut self) -> &mut Self {
+        self.auditable = true;
+        self
+    }
+
+    pub fn error(&mut self, declaration: ErrorDeclaration) -> &mut Self {
+        self.errors.push(declaration);
+        self
+    }
+
+    pub fn request_schema(&mut self, schema: Value) -> Result<&mut Self, InvalidSchema> {
+        self.request_schema = Some(self.compile("request", schema)?);
+        Ok(self)
+    }
+
+    pub fn response_schema(&mut self, schema: Value) -> Result<&mut Self, InvalidSchema> {
+        self.response_schema = Some(self.compile("response", schema)?);
+        Ok(self)
+    }
+
+    #[cfg(feature = "schemars")]
+    pub fn schemas_from<Req, Resp>(&mut self) -> Result<&mut Self, InvalidSchema>
+    where
+        Req: schemars::JsonSchema,
+        Resp: schemars::JsonSchema,
+    {
+        self.request_schema(schemars::schema_for!(Req).to_value())?
+            .response_schema(schemars::schema_for!(Resp).to_value())
+    }
+
+    fn compile(&self, kind: &str, schema: Value) -> Result<PayloadSchema, InvalidSchema> {
+        #[cfg(not(feature = "json-schema"))]
+        log::warn!(
+            "The {} schema of event {} v{} is not validated, enable the json-schema feature to validate payloads",
+            kind,
+            self.name,
+            self.version
+        );
+        PayloadSchema::new(schema).map_err(|err| InvalidSchema {
+            message: format!(
+                "{} schema for event {} v{}: {}",
+                kind, self.name, self.version, err.message
+            ),
+        })
+    }
+
+    pub fn declares(&self, error: &EventErrorType) -> bool {
+        self.errors.is_empty() || self.errors.iter().any(|e| e.matches(error))
+    }
+}
+
+pub fn error_catalog(store: &dyn EventStore) -> Value {
+    let events: Vec<Value> = store
+        .definitions()
+        .into_iter()
+        .map(|definition| {
+            json!({
+                "name": definition.name,
+                "version": definition.version,
+                "errors": definition.errors
+            })
+        })
+        .collect();
+    json!({ "events": events })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::definition::{error_catalog, ErrorDeclaration};
+    use crate::events::response_for;
+    use crate::store::SimpleEventStore;
+
+    #[test]
+    fn test_exports_error_catalog() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .error(
+                ErrorDeclaration::new("notFound", "ACCOUNT_NOT_FOUND")
+                    .description("The account does not exist")
+                    .parameters(json!({
+                        "type": "object",
+                        "properties": { "id": { "type": "integer" } }
+                    })),
+            )
+            .error(ErrorDeclaration::new("forbidden", "NOT_OWNER"));
+        store.add("account:create", 1, |req| Ok(response_for(req, "ok")));
+
+        assert_eq!(
+            json!({
+                "events": [
+                    { "name": "account:create", "version": 1, "errors": [] },
+                    {
+                        "name": "account:get",
+                        "version": 1,
+                        "errors": [
+                            {
+                                "type": "notFound",
+                                "code": "ACCOUNT_NOT_FOUND",
+                                "description": "The account does not exist",
+                                "parameters": {
+                                    "type": "object",
+                                    "properties": { "id": { "type": "integer" } }
+                                }
+                            },
+                            { "type": "forbidden", "code": "NOT_OWNER" }
+                        ]
+                    }
+                ]
+            }),
+            error_catalog(&store)
+        );
+    }
+}
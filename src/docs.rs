@@ -0,0 +1,262 @@
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::definition::{ErrorDeclaration, EventDefinition};
+use crate::store::EventStore;
+
+const ASYNCAPI_VERSION: &str = "2.6.0";
+
+#[derive(Debug, Clone)]
+pub struct DocumentInfo {
+    pub title: String,
+    pub version: String,
+    pub description: Option<String>,
+}
+
+impl DocumentInfo {
+    pub fn new(title: &str, version: &str) -> Self {
+        DocumentInfo {
+            title: String::from(title),
+            version: String::from(version),
+            description: None,
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(String::from(description));
+        self
+    }
+}
+
+pub fn asyncapi(store: &dyn EventStore, info: &DocumentInfo) -> Value {
+    let mut document_info = Map::new();
+    document_info.insert(String::from("title"), json!(info.title));
+    document_info.insert(String::from("version"), json!(info.version));
+    if let Some(description) = &info.description {
+        document_info.insert(String::from("description"), json!(description));
+    }
+
+    let channels: Map<String, Value> = store
+        .definitions()
+        .into_iter()
+        .map(|definition| (channel_name(definition), channel(definition)))
+        .collect();
+
+    json!({
+        "asyncapi": ASYNCAPI_VERSION,
+        "info": document_info,
+        "defaultContentType": "application/json",
+        "channels": channels
+    })
+}
+
+#[cfg(feature = "yaml")]
+pub fn asyncapi_yaml(store: &dyn EventStore, info: &DocumentInfo) -> Result<String, String> {
+    serde_yaml::to_string(&asyncapi(store, info)).map_err(|err| format!("{}", err))
+}
+
+pub fn markdown(store: &dyn EventStore, info: &DocumentInfo) -> String {
+    let mut out = String::new();
+    writeln!(out, "# {} ({})", info.title, info.version).unwrap();
+    if let Some(description) = &info.description {
+        writeln!(out, "\n{}", description).unwrap();
+    }
+
+    for definition in store.definitions() {
+        writeln!(out, "\n## {} (v{})", definition.name, definition.version).unwrap();
+        if definition.deprecated {
+            writeln!(out, "\n> **Deprecated**").unwrap();
+        }
+        if let Some(description) = &definition.description {
+            writeln!(out, "\n{}", description).unwrap();
+        }
+        if let Some(schema) = &definition.request_schema {
+            writeln!(out, "\n### Request payload\n").unwrap();
+            write_json_block(&mut out, schema.schema());
+        }
+        if let Some(schema) = &definition.response_schema {
+            writeln!(out, "\n### Response payload\n").unwrap();
+            write_json_block(&mut out, schema.schema());
+        }
+        if !definition.errors.is_empty() {
+            writeln!(out, "\n### Errors\n").unwrap();
+            writeln!(out, "| Type | Code | Description |").unwrap();
+            writeln!(out, "| --- | --- | --- |").unwrap();
+            for error in &definition.errors {
+                writeln!(
+                    out,
+                    "| `{}` | `{}` | {} |",
+                    error.error_type,
+                    error.code,
+                    error.description.as_deref().unwrap_or("")
+                )
+                .unwrap();
+            }
+        }
+    }
+    out
+}
+
+fn write_json_block(out: &mut String, value: &Value) {
+    writeln!(
+        out,
+        "```json\n{}\n```",
+        serde_json::to_string_pretty(value).unwrap()
+    )
+    .unwrap();
+}
+
+fn channel_name(definition: &EventDefinition) -> String {
+    format!("{}/v{}", definition.name, definition.version)
+}
+
+fn channel(definition: &EventDefinition) -> Value {
+    let mut request = Map::new();
+    request.insert(String::from("name"), json!(definition.name));
+    if let Some(schema) = &definition.request_schema {
+        request.insert(String::from("payload"), schema.schema().clone());
+    }
+
+    let mut response = Map::new();
+    response.insert(
+        String::from("name"),
+        json!(format!("{}:response", definition.name)),
+    );
+    if let Some(schema) = &definition.response_schema {
+        response.insert(String::from("payload"), schema.schema().clone());
+    }
+
+    let mut responses = vec![Value::Object(response)];
+    responses.extend(
+        definition
+            .errors
+            .iter()
+            .map(|error| error_message(definition, error)),
+    );
+
+    let mut channel = Map::new();
+    if let Some(description) = &definition.description {
+        channel.insert(String::from("description"), json!(description));
+    }
+    if definition.deprecated {
+        channel.insert(String::from("x-deprecated"), json!(true));
+    }
+    channel.insert(
+        String::from("publish"),
+        json!({
+            "operationId": format!("{}_v{}", definition.name, definition.version),
+            "message": request
+        }),
+    );
+    channel.insert(
+        String::from("subscribe"),
+        json!({ "message": { "oneOf": responses } }),
+    );
+    Value::Object(channel)
+}
+
+fn error_message(definition: &EventDefinition, error: &ErrorDeclaration) -> Value {
+    let mut message = Map::new();
+    message.insert(
+        String::from("name"),
+        json!(format!("{}:{}", definition.name, error.error_type)),
+    );
+    if let Some(description) = &error.description {
+        message.insert(String::from("description"), json!(description));
+    }
+    message.insert(
+        String::from("payload"),
+        json!({
+            "type": "object",
+            "required": ["code", "parameters"],
+            "properties": {
+                "code": { "const": error.code },
+                "parameters": error.parameters.clone().unwrap_or_else(|| json!({}))
+            }
+        }),
+    );
+    Value::Object(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::definition::ErrorDeclaration;
+    use crate::docs::{asyncapi, markdown, DocumentInfo};
+    use crate::events::response_for;
+    use crate::store::SimpleEventStore;
+
+    fn store() -> SimpleEventStore<'static> {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .description("Fetches an account")
+            .deprecated()
+            .request_schema(json!({ "type": "object" }))
+            .unwrap()
+            .error(
+                ErrorDeclaration::new("notFound", "ACCOUNT_NOT_FOUND")
+                    .description("The account does not exist"),
+            );
+        store.add("account:get", 2, |req| Ok(response_for(req, "ok")));
+        store
+    }
+
+    #[test]
+    fn test_exports_asyncapi_document() {
+        let document = asyncapi(
+            &store(),
+            &DocumentInfo::new("Accounts", "1.0.0").description("Account events"),
+        );
+
+        assert_eq!("2.6.0", document["asyncapi"]);
+        assert_eq!("Account events", document["info"]["description"]);
+
+        let channel = &document["channels"]["account:get/v1"];
+        assert_eq!("Fetches an account", channel["description"]);
+        assert_eq!(true, channel["x-deprecated"]);
+        assert_eq!(
+            json!({ "type": "object" }),
+            channel["publish"]["message"]["payload"]
+        );
+
+        let responses = channel["subscribe"]["message"]["oneOf"].as_array().unwrap();
+        assert_eq!("account:get:response", responses[0]["name"]);
+        assert_eq!("account:get:notFound", responses[1]["name"]);
+        assert_eq!(
+            "ACCOUNT_NOT_FOUND",
+            responses[1]["payload"]["properties"]["code"]["const"]
+        );
+
+        assert!(document["channels"]["account:get/v2"]["x-deprecated"].is_null());
+    }
+
+    #[test]
+    fn test_renders_markdown() {
+        let rendered = markdown(&store(), &DocumentInfo::new("Accounts", "1.0.0"));
+
+        assert!(rendered.starts_with("# Accounts (1.0.0)\n"));
+        assert!(
+            rendered.contains("\n## account:get (v1)\n\n> **Deprecated**\n\nFetches an account\n")
+        );
+        assert!(
+            rendered.contains("### Request payload\n\n```json\n{\n  \"type\": \"object\"\n}\n```")
+        );
+        assert!(
+            rendered.contains("| `notFound` | `ACCOUNT_NOT_FOUND` | The account does not exist |")
+        );
+        assert!(rendered.contains("\n## account:get (v2)\n"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_exports_asyncapi_yaml() {
+        let yaml =
+            crate::docs::asyncapi_yaml(&store(), &DocumentInfo::new("Accounts", "1.0.0")).unwrap();
+
+        assert!(yaml.contains("asyncapi: 2.6.0"));
+        assert!(yaml.contains("account:get/v1:"));
+    }
+}
@@ -1,19 +1,70 @@
 use crate::errors::EventErrorType::{
-    BadRequest, Expired, Forbidden, Generic, NotFound, ResourceDenied, Unauthorized, Unknown,
-    UserDenied,
+    BadRequest, Expired, Forbidden, Generic, NotFound, ResourceDenied, TooManyRequests,
+    Unauthorized, Unknown, UserDenied, WithSource,
 };
 use crate::events::{RequestEvent, ResponseEvent};
 use serde_json::{json, Value};
+use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
 use uuid::Uuid;
 
+pub const ERROR_TYPES: [&str; 9] = [
+    "error",
+    "badRequest",
+    "unauthorized",
+    "notFound",
+    "forbidden",
+    "userDenied",
+    "resourceDenied",
+    "expired",
+    "tooManyRequests",
+];
+
+pub const PROTOCOL_ERROR_TYPES: [&str; 2] = ["badProtocol", "eventNotFound"];
+
+pub const fn is_error_type(error_type: &str) -> bool {
+    let mut idx = 0;
+    while idx < ERROR_TYPES.len() {
+        if str_eq(ERROR_TYPES[idx], error_type) {
+            return true;
+        }
+        idx += 1;
+    }
+    false
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut idx = 0;
+    while idx < a.len() {
+        if a[idx] != b[idx] {
+            return false;
+        }
+        idx += 1;
+    }
+    true
+}
+
 #[derive(Debug, Clone)]
 pub struct EventError {
     pub code: String,
     pub parameters: Value,
 }
 
-#[derive(Debug)]
+impl EventError {
+    pub fn new(code: &str, parameters: Value) -> Self {
+        EventError {
+            code: String::from(code),
+            parameters,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum EventErrorType {
     Generic(EventError),
     BadRequest(EventError),
@@ -23,10 +74,48 @@ pub enum EventErrorType {
     UserDenied(EventError),
     ResourceDenied(EventError),
     Expired(EventError),
+    TooManyRequests(EventError),
     Unknown(String, EventError),
+    WithSource(Box<EventErrorType>, Arc<dyn Error + Send + Sync>),
 }
 
 impl EventErrorType {
+    pub fn generic(code: &str, parameters: Value) -> Self {
+        Generic(EventError::new(code, parameters))
+    }
+
+    pub fn bad_request(code: &str, parameters: Value) -> Self {
+        BadRequest(EventError::new(code, parameters))
+    }
+
+    pub fn unauthorized(code: &str, parameters: Value) -> Self {
+        Unauthorized(EventError::new(code, parameters))
+    }
+
+    pub fn not_found(code: &str, parameters: Value) -> Self {
+        NotFound(EventError::new(code, parameters))
+    }
+
+    pub fn forbidden(code: &str, parameters: Value) -> Self {
+        Forbidden(EventError::new(code, parameters))
+    }
+
+    pub fn user_denied(code: &str, parameters: Value) -> Self {
+        UserDenied(EventError::new(code, parameters))
+    }
+
+    pub fn resource_denied(code: &str, parameters: Value) -> Self {
+        ResourceDenied(EventError::new(code, parameters))
+    }
+
+    pub fn expired(code: &str, parameters: Value) -> Self {
+        Expired(EventError::new(code, parameters))
+    }
+
+    pub fn too_many_requests(code: &str, parameters: Value) -> Self {
+        TooManyRequests(EventError::new(code, parameters))
+    }
+
     pub fn new(error_type: &str, error: EventError) -> Self {
         match error_type {
             "error" => Generic(error),
@@ -37,6 +126,7 @@ impl EventErrorType {
             "userDenied" => UserDenied(error),
             "resourceDenied" => ResourceDenied(error),
             "expired" => Expired(error),
+            "tooManyRequests" => TooManyRequests(error),
             _ => Unknown(String::from(error_type), error),
         }
     }
@@ -51,23 +141,70 @@ impl EventErrorType {
             UserDenied(_) => "userDenied",
             ResourceDenied(_) => "resourceDenied",
             Expired(_) => "expired",
+            TooManyRequests(_) => "tooManyRequests",
             Unknown(value, _) => value.as_str(),
+            WithSource(error, _) => error.error_type(),
         }
     }
 
     pub fn value(&self) -> EventError {
+        self.error().clone()
+    }
+
+    pub fn error(&self) -> &EventError {
         match self {
-            Generic(err) => err.clone(),
-            BadRequest(err) => err.clone(),
-            Unauthorized(err) => err.clone(),
-            NotFound(err) => err.clone(),
-            Forbidden(err) => err.clone(),
-            UserDenied(err) => err.clone(),
-            ResourceDenied(err) => err.clone(),
-            Expired(err) => err.clone(),
-            Unknown(_value, err) => err.clone(),
+            Generic(err) => err,
+            BadRequest(err) => err,
+            Unauthorized(err) => err,
+            NotFound(err) => err,
+            Forbidden(err) => err,
+            UserDenied(err) => err,
+            ResourceDenied(err) => err,
+            Expired(err) => err,
+            TooManyRequests(err) => err,
+            Unknown(_value, err) => err,
+            WithSource(error, _) => error.error(),
         }
     }
+
+    pub fn into_error(self) -> EventError {
+        match self {
+            Generic(err) => err,
+            BadRequest(err) => err,
+            Unauthorized(err) => err,
+            NotFound(err) => err,
+            Forbidden(err) => err,
+            UserDenied(err) => err,
+            ResourceDenied(err) => err,
+            Expired(err) => err,
+            TooManyRequests(err) => err,
+            Unknown(_value, err) => err,
+            WithSource(error, _) => error.into_error(),
+        }
+    }
+
+    pub fn kind(&self) -> &EventErrorType {
+        match self {
+            WithSource(error, _) => error.kind(),
+            error => error,
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.error().code
+    }
+
+    pub fn parameters(&self) -> &Value {
+        &self.error().parameters
+    }
+
+    pub fn with_source<E: Error + Send + Sync + 'static>(self, source: E) -> Self {
+        let error = match self {
+            WithSource(error, _) => error,
+            error => Box::new(error),
+        };
+        WithSource(error, Arc::new(source))
+    }
 }
 
 impl Display for EventErrorType {
@@ -76,11 +213,27 @@ impl Display for EventErrorType {
             f,
             "error type: {:?}, code: {:?}",
             self.error_type(),
-            self.value().code
+            self.code()
         )
     }
 }
 
+impl Error for EventErrorType {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WithSource(_, source) => Some(source.as_ref() as &(dyn Error + 'static)),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for EventErrorType {
+    fn from(err: serde_json::Error) -> Self {
+        EventErrorType::bad_request("INVALID_PAYLOAD", json!({ "message": format!("{}", err) }))
+            .with_source(err)
+    }
+}
+
 pub fn event_not_found(event: &RequestEvent) -> ResponseEvent {
     ResponseEvent {
         name: String::from("eventNotFound"),
@@ -118,8 +271,48 @@ pub fn bad_protocol(err: serde_json::Error) -> ResponseEvent {
     }
 }
 
+pub fn bad_protocol_for(payload: &str, err: serde_json::Error) -> ResponseEvent {
+    let mut response = bad_protocol(err);
+    if let Ok(value) = serde_json::from_str::<Value>(payload) {
+        let uuid = |field: &str| {
+            value
+                .get(field)
+                .and_then(Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+        };
+        response.id = uuid("id").unwrap_or(response.id);
+        response.flow_id = uuid("flowId").unwrap_or(response.flow_id);
+    }
+    response
+}
+
+pub fn message_too_large(max_bytes: usize) -> ResponseEvent {
+    ResponseEvent {
+        name: String::from("badProtocol"),
+        version: 1,
+        id: Uuid::new_v4(),
+        flow_id: Uuid::new_v4(),
+        payload: json!({
+            "code": "MESSAGE_TOO_LARGE",
+            "parameters": {
+                "maxBytes": max_bytes
+            }
+        }),
+        identity: json!({}),
+        auth: json!({}),
+        metadata: json!({}),
+    }
+}
+
+pub fn batch_too_large(max_events: usize, max_bytes: usize) -> EventErrorType {
+    EventErrorType::bad_request(
+        "BATCH_TOO_LARGE",
+        json!({ "maxEvents": max_events, "maxBytes": max_bytes }),
+    )
+}
+
 pub fn error_for(event: &RequestEvent, error: &EventErrorType) -> ResponseEvent {
-    let evt_error = error.value();
+    let evt_error = error.error();
     ResponseEvent {
         name: format!("{}:{}", event.name, error.error_type()),
         version: event.version,
@@ -134,3 +327,134 @@ pub fn error_for(event: &RequestEvent, error: &EventErrorType) -> ResponseEvent
         metadata: json!({}),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::io;
+
+    use events_protocol_macros::EventError;
+    use serde_json::json;
+
+    use crate::errors::{error_for, EventErrorType};
+    use crate::events::{parse_event, response_for, RequestEvent, ResponseEvent};
+
+    #[derive(EventError)]
+    enum AccountError {
+        #[event_error(type = "notFound", code = "ACCOUNT_NOT_FOUND")]
+        NotFound {
+            account_id: u64,
+            r#type: &'static str,
+        },
+        #[event_error(type = "badRequest", code = "INVALID_AMOUNT")]
+        InvalidAmount(i64),
+        #[event_error(type = "badRequest", code = "INVALID_RANGE")]
+        InvalidRange(i64, i64),
+        #[event_error(type = "error", code = "DATABASE_UNAVAILABLE")]
+        DatabaseUnavailable,
+    }
+
+    fn find_account(account_id: u64) -> Result<u64, AccountError> {
+        Err(AccountError::NotFound {
+            account_id,
+            r#type: "savings",
+        })
+    }
+
+    fn handler(event: &RequestEvent) -> Result<ResponseEvent, EventErrorType> {
+        let account = find_account(event.payload["accountId"].as_u64().unwrap())?;
+        Ok(response_for(event, account))
+    }
+
+    #[test]
+    fn test_derived_errors_convert_into_event_error_type() {
+        let event = parse_event(
+            r#"{
+                    "name": "account:get",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": { "accountId": 42 },
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+
+        let error = handler(&event).unwrap_err();
+        assert_eq!("notFound", error.error_type());
+        assert_eq!("ACCOUNT_NOT_FOUND", error.value().code);
+        assert_eq!(
+            json!({"accountId": 42, "type": "savings"}),
+            error.value().parameters
+        );
+
+        let error = EventErrorType::from(AccountError::InvalidAmount(-1));
+        assert_eq!("badRequest", error.error_type());
+        assert_eq!(json!(-1), error.value().parameters);
+
+        let error = EventErrorType::from(AccountError::InvalidRange(1, 0));
+        assert_eq!("INVALID_RANGE", error.value().code);
+        assert_eq!(json!([1, 0]), error.value().parameters);
+
+        let error = EventErrorType::from(AccountError::DatabaseUnavailable);
+        assert_eq!("error", error.error_type());
+        assert_eq!(json!({}), error.value().parameters);
+    }
+
+    #[test]
+    fn test_builds_errors_with_borrowing_accessors() {
+        let error = EventErrorType::not_found("ACCOUNT_NOT_FOUND", json!({"id": 1}));
+
+        assert_eq!("notFound", error.error_type());
+        assert_eq!("ACCOUNT_NOT_FOUND", error.code());
+        assert_eq!(&json!({"id": 1}), error.parameters());
+        assert!(error.source().is_none());
+        assert_eq!(
+            "error type: \"notFound\", code: \"ACCOUNT_NOT_FOUND\"",
+            format!("{}", error)
+        );
+    }
+
+    #[test]
+    fn test_chains_source_without_serializing_it() {
+        let error = EventErrorType::generic("DATABASE_UNAVAILABLE", json!({}))
+            .with_source(io::Error::other("connection reset"));
+
+        assert_eq!("connection reset", error.source().unwrap().to_string());
+        assert_eq!("error", error.error_type());
+        assert!(matches!(error.kind(), EventErrorType::Generic(_)));
+
+        let event = parse_event(
+            r#"{
+                    "name": "account:get",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+        let response = error_for(&event, &error);
+
+        assert_eq!(
+            json!({"code": "DATABASE_UNAVAILABLE", "parameters": {}}),
+            response.payload
+        );
+    }
+
+    #[test]
+    fn test_converts_json_errors_into_bad_request() {
+        let json_error = serde_json::from_str::<u64>("\"nope\"").unwrap_err();
+        let error = EventErrorType::from(json_error);
+
+        assert_eq!("badRequest", error.error_type());
+        assert_eq!("INVALID_PAYLOAD", error.code());
+        assert!(error.parameters()["message"].is_string());
+        assert!(error.source().is_some());
+    }
+}
@@ -3,9 +3,10 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde_json::json;
 use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestEvent {
     pub name: String,
@@ -18,7 +19,7 @@ pub struct RequestEvent {
     pub metadata: Value,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ResponseEvent {
     pub name: String,
@@ -40,32 +41,48 @@ impl ResponseEvent {
         !self.is_success()
     }
 
-    pub fn get_error(&self) -> EventErrorType {
+    pub fn error_type(&self) -> Option<&str> {
         if !self.is_error() {
-            panic!("Cannot get error when the response is success")
+            return None;
         }
         let last_separator_idx = self.name.rfind(':').map(|idx| idx + 1).unwrap_or(0);
 
-        let error_type = &self.name[last_separator_idx..];
+        Some(&self.name[last_separator_idx..])
+    }
+
+    pub fn error(&self) -> Option<EventErrorType> {
+        let error_type = self.error_type()?;
+        let error = match (
+            self.payload.get("code").and_then(Value::as_str),
+            self.payload.get("parameters"),
+        ) {
+            (Some(code), Some(parameters)) => EventError::new(code, parameters.clone()),
+            _ => EventError::new(
+                "MALFORMED_ERROR_RESPONSE",
+                json!({ "payload": self.payload }),
+            ),
+        };
+        Some(EventErrorType::new(error_type, error))
+    }
+
+    pub fn get_error(&self) -> EventErrorType {
+        let error_type = self
+            .error_type()
+            .expect("Cannot get error when the response is success");
 
         EventErrorType::new(
             error_type,
-            EventError {
-                code: self.payload.get("code").unwrap().as_str().unwrap().into(),
-                parameters: self.payload.get("parameters").unwrap().clone(),
-            },
+            EventError::new(
+                self.payload.get("code").unwrap().as_str().unwrap(),
+                self.payload.get("parameters").unwrap().clone(),
+            ),
         )
     }
 }
 
 pub fn parse_event(payload: &str) -> Result<RequestEvent, serde_json::Error> {
-    match serde_json::from_str::<RequestEvent>(payload) {
-        Ok(event) => {
-            //todo: write a event validator to validate that its a valid event
-            Ok(event)
-        }
-        Err(err) => Err(err),
-    }
+    //todo: write a event validator to validate that its a valid event
+    serde_json::from_str::<RequestEvent>(payload)
 }
 
 pub fn response_for<T: Serialize>(event: &RequestEvent, payload: T) -> ResponseEvent {
@@ -80,3 +97,37 @@ pub fn response_for<T: Serialize>(event: &RequestEvent, payload: T) -> ResponseE
         metadata: json!({}),
     }
 }
+
+pub fn new_event<T: Serialize>(name: &str, version: u16, payload: T) -> RequestEvent {
+    RequestEvent {
+        name: String::from(name),
+        version,
+        id: Uuid::new_v4(),
+        flow_id: Uuid::new_v4(),
+        payload: serde_json::to_value(payload).unwrap(),
+        identity: json!({}),
+        auth: json!({}),
+        metadata: json!({}),
+    }
+}
+
+pub fn child_event<T: Serialize>(
+    parent: &RequestEvent,
+    name: &str,
+    version: u16,
+    payload: T,
+) -> RequestEvent {
+    RequestEvent {
+        flow_id: parent.flow_id,
+        identity: parent.identity.clone(),
+        auth: parent.auth.clone(),
+        ..new_event(name, version, payload)
+    }
+}
+
+pub(crate) fn epoch_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -1,8 +1,47 @@
+use crate::context::EventContext;
 use crate::errors::EventErrorType;
-use crate::events::{RequestEvent, ResponseEvent};
+use crate::events::{response_for, RequestEvent, ResponseEvent};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
 
 pub trait EventHandler {
     fn handle(&self, event: &RequestEvent) -> Result<ResponseEvent, EventErrorType>;
+
+    fn handle_with_context(
+        &self,
+        _context: &EventContext,
+        event: &RequestEvent,
+    ) -> Result<ResponseEvent, EventErrorType> {
+        self.handle(event)
+    }
+}
+
+impl<H: ?Sized + EventHandler> EventHandler for Box<H> {
+    fn handle(&self, event: &RequestEvent) -> Result<ResponseEvent, EventErrorType> {
+        (**self).handle(event)
+    }
+
+    fn handle_with_context(
+        &self,
+        context: &EventContext,
+        event: &RequestEvent,
+    ) -> Result<ResponseEvent, EventErrorType> {
+        (**self).handle_with_context(context, event)
+    }
+}
+
+pub trait NamedEventHandler: EventHandler {
+    const NAME: &'static str;
+    const VERSION: u16;
+}
+
+pub trait EventListener: Send + Sync {
+    fn on_event(&self, event: &RequestEvent) -> Result<(), EventErrorType>;
+}
+
+pub fn payload_of<T: DeserializeOwned>(event: &RequestEvent) -> Result<T, EventErrorType> {
+    Ok(T::deserialize(&event.payload)?)
 }
 
 pub struct FnForwardHandler<T: Fn(&RequestEvent) -> Result<ResponseEvent, EventErrorType>> {
@@ -17,10 +56,100 @@ impl<T: Fn(&RequestEvent) -> Result<ResponseEvent, EventErrorType>> FnForwardHan
     }
 }
 
-impl<T: Fn(&RequestEvent) -> Result<ResponseEvent, EventErrorType>> EventHandler
-    for FnForwardHandler<T>
+impl<T> EventHandler for FnForwardHandler<T>
+where
+    T: Fn(&RequestEvent) -> Result<ResponseEvent, EventErrorType>,
 {
     fn handle(&self, event: &RequestEvent) -> Result<ResponseEvent, EventErrorType> {
         (self.fn_handler)(event)
     }
 }
+
+pub struct FnContextHandler<T>
+where
+    T: Fn(&EventContext, &RequestEvent) -> Result<ResponseEvent, EventErrorType>,
+{
+    fn_handler: T,
+}
+
+impl<T> FnContextHandler<T>
+where
+    T: Fn(&EventContext, &RequestEvent) -> Result<ResponseEvent, EventErrorType>,
+{
+    pub fn new(handler: T) -> FnContextHandler<T> {
+        FnContextHandler {
+            fn_handler: handler,
+        }
+    }
+}
+
+impl<T> EventHandler for FnContextHandler<T>
+where
+    T: Fn(&EventContext, &RequestEvent) -> Result<ResponseEvent, EventErrorType>,
+{
+    fn handle(&self, event: &RequestEvent) -> Result<ResponseEvent, EventErrorType> {
+        self.handle_with_context(&EventContext::detached(event), event)
+    }
+
+    fn handle_with_context(
+        &self,
+        context: &EventContext,
+        event: &RequestEvent,
+    ) -> Result<ResponseEvent, EventErrorType> {
+        (self.fn_handler)(context, event)
+    }
+}
+
+pub struct FnListener<T: Fn(&RequestEvent) -> Result<(), EventErrorType>> {
+    fn_listener: T,
+}
+
+impl<T: Fn(&RequestEvent) -> Result<(), EventErrorType>> FnListener<T> {
+    pub fn new(listener: T) -> FnListener<T> {
+        FnListener {
+            fn_listener: listener,
+        }
+    }
+}
+
+impl<T> EventListener for FnListener<T>
+where
+    T: Fn(&RequestEvent) -> Result<(), EventErrorType> + Send + Sync,
+{
+    fn on_event(&self, event: &RequestEvent) -> Result<(), EventErrorType> {
+        (self.fn_listener)(event)
+    }
+}
+
+pub struct TypedHandler<Req, Resp, T>
+where
+    T: Fn(&RequestEvent, Req) -> Result<Resp, EventErrorType>,
+{
+    fn_handler: T,
+    types: PhantomData<fn(Req) -> Resp>,
+}
+
+impl<Req, Resp, T> TypedHandler<Req, Resp, T>
+where
+    T: Fn(&RequestEvent, Req) -> Result<Resp, EventErrorType>,
+{
+    pub fn new(handler: T) -> TypedHandler<Req, Resp, T> {
+        TypedHandler {
+            fn_handler: handler,
+            types: PhantomData,
+        }
+    }
+}
+
+impl<Req, Resp, T> EventHandler for TypedHandler<Req, Resp, T>
+where
+    Req: DeserializeOwned,
+    Resp: Serialize,
+    T: Fn(&RequestEvent, Req) -> Result<Resp, EventErrorType>,
+{
+    fn handle(&self, event: &RequestEvent) -> Result<ResponseEvent, EventErrorType> {
+        let payload = payload_of(event)?;
+        let response = (self.fn_handler)(event, payload)?;
+        Ok(response_for(event, response))
+    }
+}
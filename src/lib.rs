@@ -1,5 +1,31 @@
-mod errors;
+#[cfg(test)]
+extern crate self as events_protocol;
+
+pub mod audit;
+pub mod bus;
+pub mod client;
+pub mod consumer;
+pub mod context;
+pub mod deadletter;
+pub mod definition;
+pub mod docs;
+pub mod errors;
 pub mod events;
-mod handlers;
+pub mod handlers;
+pub mod migration;
 pub mod processor;
+pub mod rate_limit;
+pub mod recording;
+pub mod saga;
+pub mod schema;
 pub mod store;
+pub mod transport;
+pub mod validation;
+
+#[cfg(feature = "macros")]
+pub use events_protocol_macros::{event_handler, EventError};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+}
@@ -0,0 +1,186 @@
+use serde_json::Value;
+
+use crate::errors::EventErrorType;
+
+type Transform = Box<dyn Fn(Value) -> Result<Value, EventErrorType> + Send + Sync>;
+
+pub struct Migration {
+    upcast: Transform,
+    downcast: Option<Transform>,
+}
+
+impl Migration {
+    pub fn new<T>(upcast: T) -> Self
+    where
+        T: Fn(Value) -> Result<Value, EventErrorType> + Send + Sync + 'static,
+    {
+        Migration {
+            upcast: Box::new(upcast),
+            downcast: None,
+        }
+    }
+
+    pub fn with_downcast<T>(mut self, downcast: T) -> Self
+    where
+        T: Fn(Value) -> Result<Value, EventErrorType> + Send + Sync + 'static,
+    {
+        self.downcast = Some(Box::new(downcast));
+        self
+    }
+
+    pub fn upcast(&self, payload: Value) -> Result<Value, EventErrorType> {
+        (self.upcast)(payload)
+    }
+
+    pub fn downcast(&self, payload: Value) -> Result<Value, EventErrorType> {
+        match &self.downcast {
+            Some(downcast) => downcast(payload),
+            None => Ok(payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::errors::EventErrorType;
+    use crate::events::{new_event, response_for};
+    use crate::migration::Migration;
+    use crate::processor::EventProcessor;
+    use crate::store::SimpleEventStore;
+
+    fn processor() -> EventProcessor {
+        let mut store = SimpleEventStore::new();
+        store.add("user:create", 3, |req| {
+            if req.payload["name"] == "taken" {
+                return Err(EventErrorType::resource_denied("USER_EXISTS", json!({})));
+            }
+            Ok(response_for(
+                req,
+                json!({ "user": { "name": req.payload["name"], "email": req.payload["email"] } }),
+            ))
+        });
+        store.add_migration(
+            "user:create",
+            1,
+            Migration::new(|payload| match payload["username"].as_str() {
+                Some(username) => Ok(json!({ "name": username })),
+                None => Err(EventErrorType::bad_request("MISSING_USERNAME", json!({}))),
+            })
+            .with_downcast(|payload| Ok(json!({ "username": payload["name"] }))),
+        );
+        store.add_migration(
+            "user:create",
+            2,
+            Migration::new(|mut payload| {
+                payload["email"] = json!(null);
+                Ok(payload)
+            })
+            .with_downcast(|payload| Ok(payload["user"].clone())),
+        );
+        EventProcessor::new(Box::new(store))
+    }
+
+    fn process(version: u16, payload: Value) -> crate::events::ResponseEvent {
+        let event = new_event("user:create", version, payload);
+        processor().process_event(&serde_json::to_string(&event).unwrap())
+    }
+
+    #[test]
+    fn test_runs_chain_of_migrations() {
+        let v1 = process(1, json!({ "username": "ana" }));
+        assert_eq!("user:create:response", v1.name);
+        assert_eq!(1, v1.version);
+        assert_eq!(json!({ "username": "ana" }), v1.payload);
+
+        let v2 = process(2, json!({ "name": "ana" }));
+        assert_eq!(2, v2.version);
+        assert_eq!(json!({ "name": "ana", "email": null }), v2.payload);
+
+        let v3 = process(3, json!({ "name": "ana", "email": "ana@example.com" }));
+        assert_eq!(
+            json!({ "user": { "name": "ana", "email": "ana@example.com" } }),
+            v3.payload
+        );
+    }
+
+    #[test]
+    fn test_reports_migration_failures_on_original_version() {
+        let response = process(1, json!({}));
+        assert_eq!("user:create:badRequest", response.name);
+        assert_eq!(1, response.version);
+        assert_eq!("MISSING_USERNAME", response.get_error().code());
+
+        assert_eq!("eventNotFound", process(4, json!({})).name);
+    }
+
+    #[test]
+    fn test_returns_handler_errors_on_original_version_without_downcast() {
+        let response = process(1, json!({ "username": "taken" }));
+        assert_eq!("user:create:resourceDenied", response.name);
+        assert_eq!(1, response.version);
+        assert_eq!("USER_EXISTS", response.get_error().code());
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_validates_schemas_of_requested_and_migrated_versions() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("user:create", 2, |req| {
+                Ok(response_for(req, json!({ "name": req.payload["name"] })))
+            })
+            .request_schema(json!({
+                "type": "object",
+                "required": ["name"],
+                "properties": { "name": { "type": "string" } }
+            }))
+            .unwrap();
+        store
+            .add_migration(
+                "user:create",
+                1,
+                Migration::new(|payload| Ok(json!({ "name": payload["username"] })))
+                    .with_downcast(|payload| Ok(json!({ "username": payload["name"] }))),
+            )
+            .request_schema(json!({ "type": "object", "required": ["username"] }))
+            .unwrap()
+            .response_schema(json!({
+                "type": "object",
+                "properties": { "username": { "type": "string", "maxLength": 8 } }
+            }))
+            .unwrap();
+        let processor = EventProcessor::new(Box::new(store)).with_response_validation(true);
+        let process = |payload: Value| {
+            let event = new_event("user:create", 1, payload);
+            processor.process_event(&serde_json::to_string(&event).unwrap())
+        };
+
+        let response = process(json!({ "name": "ana" }));
+        assert_eq!("user:create:badRequest", response.name);
+        assert_eq!(1, response.version);
+        assert_eq!(
+            "",
+            response.payload["parameters"]["violations"][0]["pointer"]
+        );
+
+        let response = process(json!({ "username": 7 }));
+        assert_eq!("user:create:badRequest", response.name);
+        assert_eq!(1, response.version);
+        assert_eq!(
+            "/name",
+            response.payload["parameters"]["violations"][0]["pointer"]
+        );
+
+        let response = process(json!({ "username": "anastasia" }));
+        assert_eq!("user:create:error", response.name);
+        assert_eq!(1, response.version);
+        assert_eq!("INVALID_RESPONSE_PAYLOAD", response.get_error().code());
+
+        assert_eq!(
+            json!({ "username": "ana" }),
+            process(json!({ "username": "ana" })).payload
+        );
+    }
+}
@@ -1,31 +1,626 @@
-use crate::errors::{bad_protocol, error_for, event_not_found};
-use crate::events::{parse_event, ResponseEvent};
+use crate::audit::Auditor;
+use crate::client::{ClientError, EventClient};
+use crate::context::{call_depth_exceeded, deadline_exceeded, EventContext};
+use crate::deadletter::{DeadLetter, DeadLetterFilter, DeadLetterSink};
+use crate::definition::{CatalogEnforcement, EventDefinition};
+use crate::errors::{
+    bad_protocol, bad_protocol_for, batch_too_large, error_for, event_not_found, EventError,
+    EventErrorType,
+};
+use crate::events::{
+    epoch_millis, new_event, parse_event, response_for, RequestEvent, ResponseEvent,
+};
+use crate::rate_limit::RateLimiter;
+use crate::recording::{EventRecorder, Recording};
+#[cfg(feature = "json-schema")]
+use crate::schema::{invalid_payload, invalid_response_payload};
 use crate::store::EventStore;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+pub const BATCH_EVENT: &str = "batch";
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum BatchResponse {
+    Responses(Vec<ResponseEvent>),
+    Rejected(ResponseEvent),
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub max_events: usize,
+    pub max_bytes: usize,
+    pub concurrency: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            max_events: 100,
+            max_bytes: 1024 * 1024,
+            concurrency: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NotificationReport {
+    pub listeners: usize,
+    pub delivered: usize,
+    pub failures: Vec<EventErrorType>,
+}
+
+impl NotificationReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+pub trait ProcessorStore: EventStore {
+    fn as_store(&self) -> &dyn EventStore;
+
+    fn shared(processor: &EventProcessor<Self>) -> Option<&SyncEventProcessor>;
+}
+
+impl ProcessorStore for dyn EventStore {
+    fn as_store(&self) -> &dyn EventStore {
+        self
+    }
+
+    fn shared(_processor: &EventProcessor<Self>) -> Option<&SyncEventProcessor> {
+        None
+    }
+}
+
+impl ProcessorStore for dyn EventStore + Send + Sync {
+    fn as_store(&self) -> &dyn EventStore {
+        self
+    }
+
+    fn shared(processor: &EventProcessor<Self>) -> Option<&SyncEventProcessor> {
+        Some(processor)
+    }
+}
+
+pub(crate) trait Dispatch {
+    fn dispatch(&self, event: &RequestEvent) -> ResponseEvent;
+}
 
-pub struct EventProcessor {
-    store: Box<dyn EventStore>,
+pub type SyncEventProcessor = EventProcessor<dyn EventStore + Send + Sync>;
+
+pub struct EventProcessor<S: ?Sized = dyn EventStore> {
+    store: Box<S>,
+    rate_limiter: Option<RateLimiter>,
+    dead_letters: Option<(Box<dyn DeadLetterSink>, DeadLetterFilter)>,
+    recorder: Option<Box<dyn EventRecorder>>,
+    auditor: Option<Auditor>,
+    catalog_enforcement: CatalogEnforcement,
+    #[cfg(feature = "json-schema")]
+    response_validation: bool,
+    batch: BatchOptions,
+    max_call_depth: u32,
 }
 
 impl EventProcessor {
     pub fn new(store: Box<dyn EventStore>) -> Self {
-        EventProcessor { store }
+        EventProcessor::with_store(store)
+    }
+}
+
+impl SyncEventProcessor {
+    pub fn new_sync(store: Box<dyn EventStore + Send + Sync>) -> Self {
+        EventProcessor::with_store(store)
+    }
+
+    fn process_concurrently(
+        &self,
+        items: &[Value],
+        envelope: Option<&RequestEvent>,
+        workers: usize,
+    ) -> Vec<ResponseEvent> {
+        let next = AtomicUsize::new(0);
+        let results: Vec<Mutex<Option<ResponseEvent>>> =
+            items.iter().map(|_| Mutex::new(None)).collect();
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    if idx >= items.len() {
+                        break;
+                    }
+                    *results[idx].lock().unwrap() = Some(self.process_item(&items[idx], envelope));
+                });
+            }
+        });
+        results
+            .into_iter()
+            .map(|result| result.into_inner().unwrap().unwrap())
+            .collect()
+    }
+}
+
+impl<S: ?Sized + ProcessorStore> EventProcessor<S> {
+    fn with_store(store: Box<S>) -> Self {
+        EventProcessor {
+            store,
+            rate_limiter: None,
+            dead_letters: None,
+            recorder: None,
+            auditor: None,
+            catalog_enforcement: CatalogEnforcement::default(),
+            #[cfg(feature = "json-schema")]
+            response_validation: false,
+            batch: BatchOptions::default(),
+            max_call_depth: 8,
+        }
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn with_dead_letters(
+        mut self,
+        sink: Box<dyn DeadLetterSink>,
+        filter: DeadLetterFilter,
+    ) -> Self {
+        self.dead_letters = Some((sink, filter));
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Box<dyn EventRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn with_auditor(mut self, auditor: Auditor) -> Self {
+        self.auditor = Some(auditor);
+        self
+    }
+
+    pub fn with_catalog_enforcement(mut self, enforcement: CatalogEnforcement) -> Self {
+        self.catalog_enforcement = enforcement;
+        self
+    }
+
+    #[cfg(feature = "json-schema")]
+    pub fn with_response_validation(mut self, enabled: bool) -> Self {
+        self.response_validation = enabled;
+        self
+    }
+
+    pub fn with_batch_options(mut self, options: BatchOptions) -> Self {
+        if options.concurrency > 1 && self.shared().is_none() {
+            log::warn!(
+                "Batch concurrency {} needs a processor created with EventProcessor::new_sync, batch items will run sequentially",
+                options.concurrency
+            );
+        }
+        self.batch = options;
+        self
+    }
+
+    pub fn shared(&self) -> Option<&SyncEventProcessor> {
+        S::shared(self)
+    }
+
+    pub fn with_max_call_depth(mut self, max_call_depth: u32) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
     }
 
     pub fn process_event(&self, payload: &str) -> ResponseEvent {
-        match parse_event(payload) {
+        self.process(payload, true)
+    }
+
+    fn process(&self, payload: &str, recorded: bool) -> ResponseEvent {
+        let started_at = (epoch_millis(), Instant::now());
+        let (event, response, dispatched) = match parse_event(payload) {
             Ok(event) => {
-                let option_handler = self.store.handler_for(event.name.as_str(), event.version);
-                if let Some(handler) = option_handler {
-                    match handler.handle(&event) {
-                        Ok(response) => response,
-                        Err(err) => error_for(&event, &err),
-                    }
+                let batch = self.is_batch(&event);
+                let response = if batch {
+                    self.handle_batch(&event, payload.len())
                 } else {
-                    event_not_found(&event)
+                    self.handle(&event)
+                };
+                self.audit(&event, &response);
+                let dispatched = batch && response.is_success();
+                (Some(event), response, dispatched)
+            }
+            Err(err) => (None, bad_protocol_for(payload, err), false),
+        };
+        self.dead_letter(payload, event.as_ref(), &response);
+        // Batch items are recorded one by one and sub-events are run again when their parent is
+        // replayed, so recording either the envelope or the sub-event would run them twice.
+        if recorded && !dispatched {
+            self.record(started_at, payload, event, &response);
+        }
+        response
+    }
+
+    pub fn process_batch(&self, payload: &str) -> BatchResponse {
+        let too_large = || {
+            let batch = new_event(BATCH_EVENT, 1, json!({}));
+            BatchResponse::Rejected(error_for(&batch, &self.batch_too_large()))
+        };
+        if payload.len() > self.batch.max_bytes {
+            return too_large();
+        }
+        let items: Vec<Value> = match serde_json::from_str(payload) {
+            Ok(items) => items,
+            Err(err) => return BatchResponse::Rejected(bad_protocol(err)),
+        };
+        if items.len() > self.batch.max_events {
+            return too_large();
+        }
+        BatchResponse::Responses(self.process_items(&items, None))
+    }
+
+    fn is_batch(&self, event: &RequestEvent) -> bool {
+        event.name == BATCH_EVENT
+            && event.version == 1
+            && self.store.handler_for(BATCH_EVENT, 1).is_none()
+    }
+
+    fn batch_too_large(&self) -> EventErrorType {
+        batch_too_large(self.batch.max_events, self.batch.max_bytes)
+    }
+
+    fn handle_batch(&self, event: &RequestEvent, size: usize) -> ResponseEvent {
+        if size > self.batch.max_bytes {
+            return error_for(event, &self.batch_too_large());
+        }
+        let items = match event.payload.get("events").and_then(Value::as_array) {
+            Some(items) => items,
+            None => {
+                return error_for(
+                    event,
+                    &EventErrorType::bad_request(
+                        "INVALID_BATCH",
+                        json!({ "message": "payload.events must be an array of events" }),
+                    ),
+                )
+            }
+        };
+        if items.len() > self.batch.max_events {
+            return error_for(event, &self.batch_too_large());
+        }
+        response_for(
+            event,
+            json!({ "responses": self.process_items(items, Some(event)) }),
+        )
+    }
+
+    fn process_items(
+        &self,
+        items: &[Value],
+        envelope: Option<&RequestEvent>,
+    ) -> Vec<ResponseEvent> {
+        let workers = self.batch.concurrency.min(items.len());
+        match self.shared() {
+            Some(processor) if workers > 1 => {
+                processor.process_concurrently(items, envelope, workers)
+            }
+            _ => items
+                .iter()
+                .map(|item| self.process_item(item, envelope))
+                .collect(),
+        }
+    }
+
+    fn process_item(&self, item: &Value, envelope: Option<&RequestEvent>) -> ResponseEvent {
+        // Items of a batch event act on behalf of its sender, like sub-events of a handler.
+        let inherited;
+        let item = match (envelope, item) {
+            (Some(envelope), Value::Object(fields)) => {
+                let mut fields = fields.clone();
+                fields.insert(String::from("flowId"), json!(envelope.flow_id));
+                fields.insert(String::from("identity"), envelope.identity.clone());
+                fields.insert(String::from("auth"), envelope.auth.clone());
+                inherited = Value::Object(fields);
+                &inherited
+            }
+            _ => item,
+        };
+        if item.get("name").and_then(Value::as_str) == Some(BATCH_EVENT) {
+            if let Ok(event) = serde_json::from_value::<RequestEvent>(item.clone()) {
+                if self.is_batch(&event) {
+                    let err = EventErrorType::bad_request("NESTED_BATCH", json!({}));
+                    return error_for(&event, &err);
+                }
+            }
+        }
+        self.process_event(&item.to_string())
+    }
+
+    pub fn process_notification(&self, payload: &str) -> NotificationReport {
+        let event = match parse_event(payload) {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("Could not parse notification: {}", err);
+                let error = EventError::new(
+                    "INVALID_COMMUNICATION_PROTOCOL",
+                    json!({ "message": format!("{}", err) }),
+                );
+                return NotificationReport {
+                    listeners: 0,
+                    delivered: 0,
+                    failures: vec![EventErrorType::new("badProtocol", error).with_source(err)],
+                };
+            }
+        };
+
+        notify_listeners(self.store.as_store(), &event)
+    }
+
+    fn handle(&self, event: &RequestEvent) -> ResponseEvent {
+        let option_handler = self.store.handler_for(event.name.as_str(), event.version);
+        if let Some(handler) = option_handler {
+            if let Some(Err(err)) = self.rate_limiter.as_ref().map(|l| l.check(event)) {
+                return error_for(event, &err);
+            }
+            let definition = self
+                .store
+                .definition_for(event.name.as_str(), event.version);
+            if let Err(err) = self.validate_request(event, definition) {
+                return error_for(event, &err);
+            }
+            let context = EventContext::new(self, event);
+            if context.depth() > self.max_call_depth {
+                return error_for(event, &call_depth_exceeded(self.max_call_depth));
+            }
+            if let Some(deadline) = context.deadline().filter(|d| *d < epoch_millis()) {
+                return error_for(event, &deadline_exceeded(deadline));
+            }
+            match handler.handle_with_context(&context, event) {
+                Ok(response) => self.validate_response(event, definition, response),
+                Err(err) => {
+                    if let Some(source) = err.source() {
+                        log::warn!(
+                            "Event {} v{} failed with {}, caused by: {}",
+                            event.name,
+                            event.version,
+                            err,
+                            source
+                        );
+                    }
+                    self.enforce_catalog(event, &err);
+                    error_for(event, &err)
                 }
             }
-            Err(err) => bad_protocol(err),
+        } else {
+            self.handle_migrated(event)
+                .unwrap_or_else(|| event_not_found(event))
+        }
+    }
+
+    fn handle_migrated(&self, event: &RequestEvent) -> Option<ResponseEvent> {
+        let name = event.name.as_str();
+        let mut migrations = vec![];
+        let mut version = event.version;
+        while self.store.handler_for(name, version).is_none() {
+            migrations.push(self.store.migration_for(name, version)?);
+            version = version.checked_add(1)?;
+        }
+
+        let definition = self
+            .store
+            .definition_for(event.name.as_str(), event.version);
+        if let Err(err) = self.validate_request(event, definition) {
+            return Some(error_for(event, &err));
+        }
+        let mut payload = event.payload.clone();
+        for migration in &migrations {
+            payload = match migration.upcast(payload) {
+                Ok(payload) => payload,
+                Err(err) => return Some(error_for(event, &err)),
+            };
+        }
+        let migrated = RequestEvent {
+            version,
+            payload,
+            ..event.clone()
+        };
+
+        let mut response = self.handle(&migrated);
+        response.version = event.version;
+        if response.is_error() {
+            return Some(response);
         }
+        for migration in migrations.iter().rev() {
+            response.payload = match migration.downcast(response.payload) {
+                Ok(payload) => payload,
+                Err(err) => return Some(error_for(event, &err)),
+            };
+        }
+        Some(self.validate_response(event, definition, response))
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    fn validate_request(
+        &self,
+        _event: &RequestEvent,
+        _definition: Option<&EventDefinition>,
+    ) -> Result<(), EventErrorType> {
+        Ok(())
+    }
+
+    #[cfg(feature = "json-schema")]
+    fn validate_request(
+        &self,
+        event: &RequestEvent,
+        definition: Option<&EventDefinition>,
+    ) -> Result<(), EventErrorType> {
+        if let Some(schema) = definition.and_then(|d| d.request_schema.as_ref()) {
+            let violations = schema.validate(&event.payload);
+            if !violations.is_empty() {
+                return Err(invalid_payload(&violations));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    fn validate_response(
+        &self,
+        _event: &RequestEvent,
+        _definition: Option<&EventDefinition>,
+        response: ResponseEvent,
+    ) -> ResponseEvent {
+        response
+    }
+
+    #[cfg(feature = "json-schema")]
+    fn validate_response(
+        &self,
+        event: &RequestEvent,
+        definition: Option<&EventDefinition>,
+        response: ResponseEvent,
+    ) -> ResponseEvent {
+        if !self.response_validation || response.is_error() {
+            return response;
+        }
+        if let Some(schema) = definition.and_then(|d| d.response_schema.as_ref()) {
+            let violations = schema.validate(&response.payload);
+            if !violations.is_empty() {
+                log::error!(
+                    "Event {} v{} returned a response violating its schema: {:?}",
+                    event.name,
+                    event.version,
+                    violations
+                );
+                return error_for(event, &invalid_response_payload(&violations));
+            }
+        }
+        response
+    }
+
+    fn enforce_catalog(&self, event: &RequestEvent, err: &EventErrorType) {
+        if self.catalog_enforcement == CatalogEnforcement::Off {
+            return;
+        }
+        let declared = self
+            .store
+            .definition_for(event.name.as_str(), event.version)
+            .is_none_or(|definition| definition.declares(err));
+        if declared {
+            return;
+        }
+        match self.catalog_enforcement {
+            CatalogEnforcement::Panic => panic!(
+                "Event {} v{} returned undeclared error {}",
+                event.name, event.version, err
+            ),
+            _ => log::warn!(
+                "Event {} v{} returned undeclared error {}",
+                event.name,
+                event.version,
+                err
+            ),
+        }
+    }
+
+    fn audit(&self, event: &RequestEvent, response: &ResponseEvent) {
+        if let Some(auditor) = &self.auditor {
+            let auditable = self
+                .store
+                .definition_for(event.name.as_str(), event.version)
+                .is_some_and(|definition| definition.auditable);
+            if auditable {
+                auditor.audit(event, response);
+            }
+        }
+    }
+
+    fn dead_letter(&self, payload: &str, event: Option<&RequestEvent>, response: &ResponseEvent) {
+        if let Some((sink, filter)) = &self.dead_letters {
+            if !filter.matches(response) {
+                return;
+            }
+            let letter = DeadLetter {
+                timestamp: epoch_millis(),
+                payload,
+                event,
+                response,
+            };
+            if let Err(err) = sink.dead_letter(&letter) {
+                log::warn!("Could not dead-letter event {}: {}", response.name, err);
+            }
+        }
+    }
+
+    fn record(
+        &self,
+        (timestamp, started_at): (u64, Instant),
+        payload: &str,
+        event: Option<RequestEvent>,
+        response: &ResponseEvent,
+    ) {
+        if let Some(recorder) = &self.recorder {
+            let recording = Recording {
+                timestamp,
+                duration_micros: started_at.elapsed().as_micros() as u64,
+                payload: String::from(payload),
+                event,
+                response: response.clone(),
+            };
+            if let Err(err) = recorder.record(&recording) {
+                log::warn!("Could not record event {}: {}", response.name, err);
+            }
+        }
+    }
+}
+
+pub(crate) fn notify_listeners(store: &dyn EventStore, event: &RequestEvent) -> NotificationReport {
+    let listeners = store.listeners_for(event.name.as_str(), event.version);
+    let failures: Vec<EventErrorType> = listeners
+        .iter()
+        .filter_map(|listener| listener.on_event(event).err())
+        .collect();
+    if listeners.is_empty() {
+        log::debug!(
+            "Notification {} v{} has no listeners",
+            event.name,
+            event.version
+        );
+    }
+    for err in &failures {
+        log::warn!(
+            "Listener of notification {} v{} failed with {}",
+            event.name,
+            event.version,
+            err
+        );
+    }
+    NotificationReport {
+        listeners: listeners.len(),
+        delivered: listeners.len() - failures.len(),
+        failures,
+    }
+}
+
+impl<S: ?Sized + ProcessorStore> Dispatch for EventProcessor<S> {
+    fn dispatch(&self, event: &RequestEvent) -> ResponseEvent {
+        self.process(&serde_json::to_string(event).unwrap(), false)
+    }
+}
+
+impl<S: ?Sized + ProcessorStore> EventClient for EventProcessor<S> {
+    fn endpoint(&self) -> &str {
+        "local"
+    }
+
+    fn send(&self, event: &RequestEvent) -> Result<ResponseEvent, ClientError> {
+        let payload = serde_json::to_string(event)
+            .map_err(|err| ClientError::Transport(format!("{}", err)))?;
+        Ok(self.process_event(&payload))
     }
 }
 
@@ -33,13 +628,20 @@ impl EventProcessor {
 mod tests {
     use serde_json::json;
 
+    use crate::definition::{CatalogEnforcement, ErrorDeclaration};
     use crate::errors::EventErrorType::{
         Expired, Forbidden, NotFound, ResourceDenied, Unknown, UserDenied,
     };
     use crate::errors::{EventError, EventErrorType};
     use crate::events::response_for;
-    use crate::processor::EventProcessor;
+    use crate::processor::{BatchOptions, BatchResponse, EventProcessor, SyncEventProcessor};
+    use crate::rate_limit::{RateLimit, RateLimiter};
+    use crate::recording::{EventRecorder, Recording};
     use crate::store::SimpleEventStore;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
     use EventErrorType::{BadRequest, Unauthorized};
 
     #[test]
@@ -403,4 +1005,348 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_rejects_rate_limited_event() {
+        let mut store = SimpleEventStore::new();
+        store.add("event:test", 1, |req| Ok(response_for(req, "ok")));
+
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter.limit("event:test", 1, RateLimit::per_identity("userId", 1, 0.0));
+
+        let event_processor = EventProcessor::new(Box::new(store)).with_rate_limiter(rate_limiter);
+
+        let raw_event = r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": { "userId": 1 },
+                    "auth": {}
+                }"#;
+
+        assert!(event_processor.process_event(raw_event).is_success());
+
+        let response_event = event_processor.process_event(raw_event);
+
+        assert!(response_event.is_error());
+        assert_eq!("event:test:tooManyRequests", response_event.name);
+
+        let error = response_event.get_error();
+        assert_eq!("tooManyRequests", error.error_type());
+        assert_eq!("RATE_LIMIT_EXCEEDED", error.value().code);
+    }
+
+    #[test]
+    #[should_panic(expected = "returned undeclared error")]
+    fn test_flags_undeclared_errors() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("event:test", 1, |_req| {
+                Err(EventErrorType::forbidden("NOT_OWNER", json!({})))
+            })
+            .error(ErrorDeclaration::new("notFound", "NOT_FOUND"));
+
+        let event_processor = EventProcessor::new(Box::new(store))
+            .with_catalog_enforcement(CatalogEnforcement::Panic);
+
+        let raw_event = r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#;
+
+        event_processor.process_event(raw_event);
+    }
+
+    #[test]
+    fn test_accepts_declared_errors() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("event:test", 1, |_req| {
+                Err(EventErrorType::not_found("NOT_FOUND", json!({})))
+            })
+            .error(ErrorDeclaration::new("notFound", "NOT_FOUND"));
+
+        let event_processor = EventProcessor::new(Box::new(store))
+            .with_catalog_enforcement(CatalogEnforcement::Panic);
+
+        let raw_event = r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#;
+
+        let response_event = event_processor.process_event(raw_event);
+
+        assert_eq!("notFound", response_event.get_error().error_type());
+    }
+
+    #[test]
+    fn test_fans_out_notifications_to_all_listeners() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut store = SimpleEventStore::new();
+        for _ in 0..2 {
+            let calls = calls.clone();
+            store.add_listener("user:created", 1, move |_event| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        }
+        store.add_listener("user:created", 1, |_event| {
+            Err(EventErrorType::generic("MAILER_DOWN", json!({})))
+        });
+
+        let event_processor = EventProcessor::new(Box::new(store));
+
+        let raw_event = r#"{
+                    "name": "user:created",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#;
+
+        let report = event_processor.process_notification(raw_event);
+
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+        assert_eq!(3, report.listeners);
+        assert_eq!(2, report.delivered);
+        assert!(!report.is_success());
+        assert_eq!("MAILER_DOWN", report.failures[0].code());
+
+        let report = event_processor.process_notification("{");
+        assert_eq!("badProtocol", report.failures[0].error_type());
+    }
+
+    fn batch_item(name: &str, id: u32, delay: u64) -> serde_json::Value {
+        json!({
+            "name": name,
+            "version": 1,
+            "id": format!("f467e03c-abab-4c2f-b4cf-{:012}", id),
+            "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+            "payload": { "delay": delay },
+            "metadata": {},
+            "identity": {},
+            "auth": {}
+        })
+    }
+
+    fn batch_processor(options: BatchOptions) -> SyncEventProcessor {
+        let mut store = SimpleEventStore::new_sync();
+        store.add("event:test", 1, |req| {
+            let delay = req.payload["delay"].as_u64().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+            Ok(response_for(req, delay))
+        });
+        EventProcessor::new_sync(Box::new(store)).with_batch_options(options)
+    }
+
+    #[test]
+    fn test_runs_batches_sequentially_with_handlers_that_are_not_thread_safe() {
+        let calls = Rc::new(Cell::new(0));
+        let mut store = SimpleEventStore::new();
+        let counter = calls.clone();
+        store.add("event:test", 1, move |req| {
+            counter.set(counter.get() + 1);
+            Ok(response_for(req, counter.get()))
+        });
+        let event_processor =
+            EventProcessor::new(Box::new(store)).with_batch_options(BatchOptions {
+                concurrency: 3,
+                ..Default::default()
+            });
+        let batch = json!([
+            batch_item("event:test", 1, 0),
+            batch_item("event:test", 2, 0)
+        ]);
+
+        match event_processor.process_batch(&batch.to_string()) {
+            BatchResponse::Responses(responses) => {
+                assert_eq!(json!(1), responses[0].payload);
+                assert_eq!(json!(2), responses[1].payload);
+            }
+            BatchResponse::Rejected(response) => panic!("batch rejected: {:?}", response),
+        }
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_processes_batch_array_in_order() {
+        let event_processor = batch_processor(BatchOptions {
+            concurrency: 3,
+            ..Default::default()
+        });
+        let batch = json!([
+            batch_item("event:test", 1, 30),
+            batch_item("event:missing", 2, 0),
+            { "name": "event:test" },
+            batch_item("event:test", 4, 0)
+        ]);
+
+        let responses = match event_processor.process_batch(&batch.to_string()) {
+            BatchResponse::Responses(responses) => responses,
+            other => panic!("unexpected batch response: {:?}", other),
+        };
+
+        let names: Vec<_> = responses.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            vec![
+                "event:test:response",
+                "eventNotFound",
+                "badProtocol",
+                "event:test:response"
+            ],
+            names
+        );
+        assert_eq!(30, responses[0].payload);
+        assert!(responses[3].id.to_string().ends_with("000000000004"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_batches() {
+        let event_processor = batch_processor(BatchOptions {
+            max_events: 1,
+            ..Default::default()
+        });
+        let batch = json!([
+            batch_item("event:test", 1, 0),
+            batch_item("event:test", 2, 0)
+        ]);
+
+        let rejected = |response| match response {
+            BatchResponse::Rejected(response) => response,
+            other => panic!("unexpected batch response: {:?}", other),
+        };
+        let response = rejected(event_processor.process_batch(&batch.to_string()));
+        assert_eq!("batch:badRequest", response.name);
+        assert_eq!("BATCH_TOO_LARGE", response.get_error().code());
+
+        let mut envelope = batch_item("batch", 9, 0);
+        envelope["payload"] = json!({ "events": batch });
+        let response = event_processor.process_event(&envelope.to_string());
+        assert_eq!("batch:badRequest", response.name);
+        assert_eq!("BATCH_TOO_LARGE", response.get_error().code());
+
+        let event_processor = batch_processor(BatchOptions {
+            max_bytes: 16,
+            ..Default::default()
+        });
+        let error = rejected(event_processor.process_batch(&batch.to_string())).get_error();
+        assert_eq!("BATCH_TOO_LARGE", error.code());
+        assert_eq!(
+            json!({ "maxEvents": 100, "maxBytes": 16 }),
+            *error.parameters()
+        );
+        let error = event_processor
+            .process_event(&envelope.to_string())
+            .get_error();
+        assert_eq!("BATCH_TOO_LARGE", error.code());
+        assert_eq!(
+            json!({ "maxEvents": 100, "maxBytes": 16 }),
+            *error.parameters()
+        );
+    }
+
+    #[test]
+    fn test_processes_batch_event() {
+        let event_processor = batch_processor(BatchOptions::default());
+        let mut batch = batch_item("batch", 9, 0);
+        batch["payload"] = json!({
+            "events": [batch_item("event:test", 1, 0), batch_item("batch", 2, 0)]
+        });
+
+        let response = event_processor.process_event(&batch.to_string());
+
+        assert_eq!("batch:response", response.name);
+        let responses = response.payload["responses"].as_array().unwrap();
+        assert_eq!("event:test:response", responses[0]["name"]);
+        assert_eq!("batch:badRequest", responses[1]["name"]);
+        assert_eq!("NESTED_BATCH", responses[1]["payload"]["code"]);
+
+        batch["payload"] = json!({});
+        let response = event_processor.process_event(&batch.to_string());
+        assert_eq!("INVALID_BATCH", response.get_error().code());
+    }
+
+    #[test]
+    fn test_batch_items_inherit_flow_identity_and_auth_of_the_envelope() {
+        let mut store = SimpleEventStore::new_sync();
+        store.add("whoami", 1, |req| {
+            Ok(response_for(
+                req,
+                json!({ "flowId": req.flow_id, "identity": req.identity, "auth": req.auth }),
+            ))
+        });
+        let event_processor = EventProcessor::new_sync(Box::new(store));
+        let mut item = batch_item("whoami", 1, 0);
+        item["flowId"] = json!("00000000-0000-0000-0000-000000000000");
+        item["identity"] = json!({ "user": "admin" });
+        item["auth"] = json!({ "roles": ["admin"] });
+        let mut batch = batch_item("batch", 9, 0);
+        batch["identity"] = json!({ "user": "alice" });
+        batch["auth"] = json!({ "roles": ["user"] });
+        batch["payload"] = json!({ "events": [item] });
+
+        let response = event_processor.process_event(&batch.to_string());
+
+        assert_eq!(
+            json!({
+                "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                "identity": { "user": "alice" },
+                "auth": { "roles": ["user"] }
+            }),
+            response.payload["responses"][0]["payload"]
+        );
+    }
+
+    #[test]
+    fn test_records_batch_items_but_not_the_envelope() {
+        struct CollectingRecorder(Arc<Mutex<Vec<String>>>);
+
+        impl EventRecorder for CollectingRecorder {
+            fn record(&self, recording: &Recording) -> std::io::Result<()> {
+                self.0.lock().unwrap().push(recording.response.name.clone());
+                Ok(())
+            }
+        }
+
+        let recorded = Arc::new(Mutex::new(vec![]));
+        let event_processor = batch_processor(BatchOptions::default())
+            .with_recorder(Box::new(CollectingRecorder(recorded.clone())));
+        let mut batch = batch_item("batch", 9, 0);
+        batch["payload"] = json!({
+            "events": [batch_item("event:test", 1, 0), batch_item("event:test", 2, 0)]
+        });
+
+        assert!(event_processor
+            .process_event(&batch.to_string())
+            .is_success());
+        batch["payload"] = json!({});
+        assert!(event_processor.process_event(&batch.to_string()).is_error());
+
+        assert_eq!(
+            vec![
+                "event:test:response",
+                "event:test:response",
+                "batch:badRequest"
+            ],
+            *recorded.lock().unwrap()
+        );
+    }
 }
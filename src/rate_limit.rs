@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::errors::{EventError, EventErrorType};
+use crate::events::RequestEvent;
+
+const MAX_TRACKED_KEYS: usize = 10_000;
+const EVICTION_BATCH: usize = MAX_TRACKED_KEYS / 10;
+
+#[derive(Debug, Clone)]
+pub enum RateLimitKey {
+    Global,
+    Identity(String),
+    Auth(String),
+}
+
+impl RateLimitKey {
+    fn resolve(&self, event: &RequestEvent) -> Option<String> {
+        let value = match self {
+            RateLimitKey::Global => return Some(String::new()),
+            RateLimitKey::Identity(field) => event.identity.get(field),
+            RateLimitKey::Auth(field) => event.auth.get(field),
+        };
+        match value {
+            Some(Value::String(value)) => Some(value.clone()),
+            Some(Value::Null) | None => None,
+            Some(value) => Some(value.to_string()),
+        }
+    }
+
+    fn field(&self) -> Option<String> {
+        match self {
+            RateLimitKey::Global => None,
+            RateLimitKey::Identity(field) => Some(format!("identity.{}", field)),
+            RateLimitKey::Auth(field) => Some(format!("auth.{}", field)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    pub key: RateLimitKey,
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+impl RateLimit {
+    pub fn new(key: RateLimitKey, capacity: u32, refill_per_second: f64) -> Self {
+        RateLimit {
+            key,
+            capacity,
+            refill_per_second,
+        }
+    }
+
+    pub fn global(capacity: u32, refill_per_second: f64) -> Self {
+        RateLimit::new(RateLimitKey::Global, capacity, refill_per_second)
+    }
+
+    pub fn per_identity(field: &str, capacity: u32, refill_per_second: f64) -> Self {
+        RateLimit::new(
+            RateLimitKey::Identity(String::from(field)),
+            capacity,
+            refill_per_second,
+        )
+    }
+
+    pub fn per_auth(field: &str, capacity: u32, refill_per_second: f64) -> Self {
+        RateLimit::new(
+            RateLimitKey::Auth(String::from(field)),
+            capacity,
+            refill_per_second,
+        )
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            tokens: f64::from(limit.capacity),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let refilled = self.tokens + elapsed.as_secs_f64() * limit.refill_per_second;
+        self.tokens = refilled.min(f64::from(limit.capacity));
+        self.last_refill = now;
+    }
+
+    fn try_acquire(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Option<Duration>> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing = 1.0 - self.tokens;
+        Err(Duration::try_from_secs_f64(missing / limit.refill_per_second).ok())
+    }
+
+    fn is_full_at(&self, limit: &RateLimit, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens + elapsed.as_secs_f64() * limit.refill_per_second >= f64::from(limit.capacity)
+    }
+}
+
+pub struct RateLimiter {
+    limits: HashMap<(String, u16), RateLimit>,
+    buckets: Mutex<HashMap<(String, u16, String), TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        RateLimiter {
+            limits: HashMap::new(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limit(&mut self, name: &str, version: u16, limit: RateLimit) {
+        self.limits.insert((String::from(name), version), limit);
+    }
+
+    pub fn check(&self, event: &RequestEvent) -> Result<(), EventErrorType> {
+        self.check_at(event, Instant::now())
+    }
+
+    fn check_at(&self, event: &RequestEvent, now: Instant) -> Result<(), EventErrorType> {
+        let limit = match self.limits.get(&(event.name.clone(), event.version)) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let key = match limit.key.resolve(event) {
+            Some(key) => (event.name.clone(), event.version, key),
+            None => return Err(rate_limit_key_missing(event, &limit.key)),
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(&key) {
+            // Evicting in batches keeps the scan over all buckets off most requests.
+            self.evict_full_buckets(&mut buckets, now);
+            let target = MAX_TRACKED_KEYS - EVICTION_BATCH;
+            if buckets.len() > target {
+                let excess = buckets.len() - target;
+                evict_least_recently_used(&mut buckets, excess);
+            }
+        }
+        let bucket = buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(limit, now));
+
+        bucket
+            .try_acquire(limit, now)
+            .map_err(|retry_after| rate_limit_exceeded(event, limit, retry_after))
+    }
+
+    fn evict_full_buckets(
+        &self,
+        buckets: &mut HashMap<(String, u16, String), TokenBucket>,
+        now: Instant,
+    ) {
+        let limits = &self.limits;
+        buckets.retain(
+            |(name, version, _), bucket| match limits.get(&(name.clone(), *version)) {
+                Some(limit) => !bucket.is_full_at(limit, now),
+                None => false,
+            },
+        );
+    }
+}
+
+fn evict_least_recently_used(
+    buckets: &mut HashMap<(String, u16, String), TokenBucket>,
+    count: usize,
+) {
+    let mut by_age: Vec<(Instant, &(String, u16, String))> = buckets
+        .iter()
+        .map(|(key, bucket)| (bucket.last_refill, key))
+        .collect();
+    by_age.select_nth_unstable_by_key(count - 1, |(last_refill, _)| *last_refill);
+    let oldest: Vec<(String, u16, String)> = by_age[..count]
+        .iter()
+        .map(|(_, key)| (*key).clone())
+        .collect();
+    for key in oldest {
+        buckets.remove(&key);
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn rate_limit_key_missing(event: &RequestEvent, key: &RateLimitKey) -> EventErrorType {
+    EventErrorType::BadRequest(EventError::new(
+        "RATE_LIMIT_KEY_MISSING",
+        json!({
+            "event": event.name,
+            "version": event.version,
+            "field": key.field()
+        }),
+    ))
+}
+
+fn rate_limit_exceeded(
+    event: &RequestEvent,
+    limit: &RateLimit,
+    retry_after: Option<Duration>,
+) -> EventErrorType {
+    EventErrorType::TooManyRequests(EventError::new(
+        "RATE_LIMIT_EXCEEDED",
+        json!({
+            "event": event.name,
+            "version": event.version,
+            "limit": limit.capacity,
+            "retryAfterMillis": retry_after.map(|duration| duration.as_millis() as u64)
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use crate::events::{parse_event, RequestEvent};
+    use crate::rate_limit::{RateLimit, RateLimiter, EVICTION_BATCH, MAX_TRACKED_KEYS};
+
+    fn event_for_user(user_id: &str) -> RequestEvent {
+        let mut event = parse_event(
+            r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#,
+        )
+        .unwrap();
+        event.identity = json!({ "userId": user_id });
+        event
+    }
+
+    #[test]
+    fn test_rejects_requests_over_the_limit() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::per_identity("userId", 2, 1.0));
+
+        let now = Instant::now();
+        let event = event_for_user("1");
+
+        assert!(limiter.check_at(&event, now).is_ok());
+        assert!(limiter.check_at(&event, now).is_ok());
+
+        let error = limiter.check_at(&event, now).unwrap_err();
+        assert_eq!("tooManyRequests", error.error_type());
+        assert_eq!("RATE_LIMIT_EXCEEDED", error.value().code);
+        assert_eq!(1000, error.value().parameters["retryAfterMillis"]);
+    }
+
+    #[test]
+    fn test_refills_tokens_over_time() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::per_identity("userId", 1, 2.0));
+
+        let now = Instant::now();
+        let event = event_for_user("1");
+
+        assert!(limiter.check_at(&event, now).is_ok());
+        assert!(limiter.check_at(&event, now).is_err());
+        assert!(limiter
+            .check_at(&event, now + Duration::from_millis(500))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_keeps_one_bucket_per_identity() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::per_identity("userId", 1, 0.0));
+
+        let now = Instant::now();
+
+        assert!(limiter.check_at(&event_for_user("1"), now).is_ok());
+        assert!(limiter.check_at(&event_for_user("2"), now).is_ok());
+
+        let error = limiter.check_at(&event_for_user("1"), now).unwrap_err();
+        assert!(error.value().parameters["retryAfterMillis"].is_null());
+    }
+
+    #[test]
+    fn test_ignores_events_without_limit() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:other", 1, RateLimit::global(0, 0.0));
+
+        assert!(limiter.check(&event_for_user("1")).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_events_without_the_key_field() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::per_identity("userId", 5, 1.0));
+
+        let mut event = event_for_user("1");
+        event.identity = json!({});
+
+        let error = limiter.check(&event).unwrap_err();
+        assert_eq!("badRequest", error.error_type());
+        assert_eq!("RATE_LIMIT_KEY_MISSING", error.value().code);
+        assert_eq!("identity.userId", error.value().parameters["field"]);
+    }
+
+    #[test]
+    fn test_bounds_the_number_of_tracked_keys() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::per_identity("userId", 2, 0.0));
+
+        let now = Instant::now();
+        for user_id in 0..=MAX_TRACKED_KEYS {
+            let at = now + Duration::from_millis(user_id as u64);
+            assert!(limiter
+                .check_at(&event_for_user(&user_id.to_string()), at)
+                .is_ok());
+        }
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(MAX_TRACKED_KEYS - EVICTION_BATCH + 1, buckets.len());
+        let newest = (String::from("event:test"), 1, MAX_TRACKED_KEYS.to_string());
+        assert!(buckets.contains_key(&newest));
+        assert!(!buckets.contains_key(&(String::from("event:test"), 1, String::from("0"))));
+    }
+
+    #[test]
+    fn test_reports_no_retry_time_when_refill_is_too_slow_to_represent() {
+        let mut limiter = RateLimiter::new();
+        limiter.limit("event:test", 1, RateLimit::global(1, 1e-20));
+
+        let now = Instant::now();
+        assert!(limiter.check_at(&event_for_user("1"), now).is_ok());
+
+        let error = limiter.check_at(&event_for_user("1"), now).unwrap_err();
+        assert!(error.value().parameters["retryAfterMillis"].is_null());
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::events::{RequestEvent, ResponseEvent};
+use crate::processor::{EventProcessor, ProcessorStore};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    pub timestamp: u64,
+    pub duration_micros: u64,
+    pub payload: String,
+    pub event: Option<RequestEvent>,
+    pub response: ResponseEvent,
+}
+
+pub trait EventRecorder: Send + Sync {
+    fn record(&self, recording: &Recording) -> io::Result<()>;
+}
+
+pub struct JsonLinesRecorder {
+    file: Mutex<File>,
+}
+
+impl JsonLinesRecorder {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesRecorder {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl EventRecorder for JsonLinesRecorder {
+    fn record(&self, recording: &Recording) -> io::Result<()> {
+        let mut line = serde_json::to_vec(recording)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&line)?;
+        file.flush()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    pub ignored_fields: BTreeSet<String>,
+}
+
+impl ReplayOptions {
+    pub fn ignoring(pointers: &[&str]) -> Self {
+        ReplayOptions {
+            ignored_fields: pointers.iter().map(|p| String::from(*p)).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub pointer: String,
+    pub recorded: Value,
+    pub replayed: Value,
+}
+
+#[derive(Debug)]
+pub struct ReplayResult {
+    pub line: usize,
+    pub recorded: ResponseEvent,
+    pub replayed: ResponseEvent,
+    pub differences: Vec<Difference>,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub results: Vec<ReplayResult>,
+}
+
+impl ReplayReport {
+    pub fn is_identical(&self) -> bool {
+        self.results.iter().all(|r| r.differences.is_empty())
+    }
+
+    pub fn mismatches(&self) -> Vec<&ReplayResult> {
+        self.results
+            .iter()
+            .filter(|r| !r.differences.is_empty())
+            .collect()
+    }
+}
+
+pub fn replay<S: ?Sized + ProcessorStore, P: AsRef<Path>>(
+    processor: &EventProcessor<S>,
+    path: P,
+    options: &ReplayOptions,
+) -> io::Result<ReplayReport> {
+    replay_from(processor, BufReader::new(File::open(path)?), options)
+}
+
+pub fn replay_from<S: ?Sized + ProcessorStore, R: BufRead>(
+    processor: &EventProcessor<S>,
+    reader: R,
+    options: &ReplayOptions,
+) -> io::Result<ReplayReport> {
+    let mut report = ReplayReport::default();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recording: Recording = serde_json::from_str(&line)?;
+        let replayed = processor.process_event(&recording.payload);
+
+        let differences = diff(
+            &serde_json::to_value(&recording.response)?,
+            &serde_json::to_value(&replayed)?,
+            options,
+        );
+        report.results.push(ReplayResult {
+            line: idx + 1,
+            recorded: recording.response,
+            replayed,
+            differences,
+        });
+    }
+    Ok(report)
+}
+
+pub fn diff(recorded: &Value, replayed: &Value, options: &ReplayOptions) -> Vec<Difference> {
+    let mut differences = vec![];
+    diff_at(String::new(), recorded, replayed, options, &mut differences);
+    differences
+}
+
+fn diff_at(
+    pointer: String,
+    recorded: &Value,
+    replayed: &Value,
+    options: &ReplayOptions,
+    differences: &mut Vec<Difference>,
+) {
+    if options.ignored_fields.contains(&pointer) {
+        return;
+    }
+    match (recorded, replayed) {
+        (Value::Object(recorded), Value::Object(replayed)) => {
+            let keys: BTreeSet<&String> = recorded.keys().chain(replayed.keys()).collect();
+            for key in keys {
+                diff_at(
+                    format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1")),
+                    recorded.get(key).unwrap_or(&Value::Null),
+                    replayed.get(key).unwrap_or(&Value::Null),
+                    options,
+                    differences,
+                );
+            }
+        }
+        (Value::Array(recorded), Value::Array(replayed)) => {
+            for idx in 0..recorded.len().max(replayed.len()) {
+                diff_at(
+                    format!("{}/{}", pointer, idx),
+                    recorded.get(idx).unwrap_or(&Value::Null),
+                    replayed.get(idx).unwrap_or(&Value::Null),
+                    options,
+                    differences,
+                );
+            }
+        }
+        _ if recorded != replayed => differences.push(Difference {
+            pointer,
+            recorded: recorded.clone(),
+            replayed: replayed.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::fs;
+    use std::rc::Rc;
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::events::response_for;
+    use crate::processor::EventProcessor;
+    use crate::recording::{diff, replay, JsonLinesRecorder, ReplayOptions};
+    use crate::store::SimpleEventStore;
+
+    const RAW_EVENT: &str = r#"{
+                    "name": "event:test",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {},
+                    "identity": {},
+                    "auth": {}
+                }"#;
+
+    #[test]
+    fn test_records_and_replays_event_traffic() {
+        let path = std::env::temp_dir().join(format!("recording-{}.jsonl", Uuid::new_v4()));
+
+        let mut store = SimpleEventStore::new();
+        store.add("event:test", 1, |req| {
+            Ok(response_for(req, json!({"status": "ok"})))
+        });
+        let recording_processor = EventProcessor::new(Box::new(store))
+            .with_recorder(Box::new(JsonLinesRecorder::open(&path).unwrap()));
+
+        recording_processor.process_event(RAW_EVENT);
+        recording_processor.process_event("not an event");
+
+        let mut store = SimpleEventStore::new();
+        store.add("event:test", 1, |req| {
+            Ok(response_for(req, json!({"status": "changed"})))
+        });
+        let replay_processor = EventProcessor::new(Box::new(store));
+
+        let report = replay(
+            &replay_processor,
+            &path,
+            &ReplayOptions::ignoring(&["/id", "/flowId"]),
+        )
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(2, report.results.len());
+        assert!(!report.is_identical());
+
+        let mismatches = report.mismatches();
+        assert_eq!(1, mismatches.len());
+        assert_eq!(1, mismatches[0].line);
+        assert_eq!("/payload/status", mismatches[0].differences[0].pointer);
+        assert_eq!("ok", mismatches[0].differences[0].recorded);
+        assert_eq!("changed", mismatches[0].differences[0].replayed);
+    }
+
+    #[test]
+    fn test_replays_sub_events_only_through_their_parent() {
+        let path = std::env::temp_dir().join(format!("recording-{}.jsonl", Uuid::new_v4()));
+        let processor = |calls: Rc<Cell<u32>>| {
+            let mut store = SimpleEventStore::new();
+            store.add("account:get", 1, move |req| {
+                calls.set(calls.get() + 1);
+                Ok(response_for(req, json!({ "calls": calls.get() })))
+            });
+            store.add_with_context("event:test", 1, |context, req| {
+                let account: serde_json::Value = context.call("account:get", 1, json!({}))?;
+                Ok(response_for(req, account))
+            });
+            EventProcessor::new(Box::new(store))
+        };
+
+        let recording_processor = processor(Rc::new(Cell::new(0)))
+            .with_recorder(Box::new(JsonLinesRecorder::open(&path).unwrap()));
+        assert!(recording_processor.process_event(RAW_EVENT).is_success());
+        assert_eq!(1, fs::read_to_string(&path).unwrap().lines().count());
+
+        let calls = Rc::new(Cell::new(0));
+        let report = replay(
+            &processor(calls.clone()),
+            &path,
+            &ReplayOptions::ignoring(&["/id", "/flowId"]),
+        )
+        .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(1, calls.get());
+        assert_eq!(1, report.results.len());
+        assert!(report.is_identical());
+    }
+
+    #[test]
+    fn test_diff_reports_missing_fields_and_array_items() {
+        let differences = diff(
+            &json!({"a/b": 1, "list": [1, 2]}),
+            &json!({"list": [1]}),
+            &ReplayOptions::default(),
+        );
+
+        let pointers: Vec<&str> = differences.iter().map(|d| d.pointer.as_str()).collect();
+        assert_eq!(vec!["/a~1b", "/list/1"], pointers);
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::client::EventClient;
+use crate::context::{EventContext, CALL_DEPTH};
+use crate::events::{epoch_millis, RequestEvent, ResponseEvent};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Compensation {
+    pub name: String,
+    pub version: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SagaStep {
+    pub name: String,
+    pub version: u16,
+    pub payload: Value,
+    pub compensation: Option<Compensation>,
+}
+
+impl SagaStep {
+    pub fn new<T: Serialize>(name: &str, version: u16, payload: T) -> Self {
+        SagaStep {
+            name: String::from(name),
+            version,
+            payload: serde_json::to_value(payload).unwrap(),
+            compensation: None,
+        }
+    }
+
+    pub fn with_compensation(mut self, name: &str, version: u16) -> Self {
+        self.compensation = Some(Compensation {
+            name: String::from(name),
+            version,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Saga {
+    name: String,
+    steps: Vec<SagaStep>,
+}
+
+impl Saga {
+    pub fn new(name: &str) -> Self {
+        Saga {
+            name: String::from(name),
+            steps: vec![],
+        }
+    }
+
+    pub fn with_step(mut self, step: SagaStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SagaStatus {
+    Running,
+    Completed,
+    Compensating,
+    Compensated,
+    Failed,
+}
+
+impl SagaStatus {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, SagaStatus::Running | SagaStatus::Compensating)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Pending,
+    InFlight,
+    Completed,
+    Failed,
+    Compensated,
+    CompensationFailed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StepState {
+    pub name: String,
+    pub version: u16,
+    pub payload: Value,
+    pub compensation: Option<Compensation>,
+    pub status: StepStatus,
+    pub response: Option<Value>,
+    pub error: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SagaState {
+    pub id: Uuid,
+    pub name: String,
+    pub flow_id: Uuid,
+    pub identity: Value,
+    pub auth: Value,
+    #[serde(default)]
+    pub metadata: Value,
+    pub status: SagaStatus,
+    pub steps: Vec<StepState>,
+    pub updated_at: u64,
+}
+
+impl SagaState {
+    fn new(saga: &Saga, flow_id: Uuid, identity: Value, auth: Value, metadata: Value) -> Self {
+        SagaState {
+            id: Uuid::new_v4(),
+            name: saga.name.clone(),
+            flow_id,
+            identity,
+            auth,
+            metadata,
+            status: SagaStatus::Running,
+            steps: saga
+                .steps
+                .iter()
+                .map(|step| StepState {
+                    name: step.name.clone(),
+                    version: step.version,
+                    payload: step.payload.clone(),
+                    compensation: step.compensation.clone(),
+                    status: StepStatus::Pending,
+                    response: None,
+                    error: None,
+                })
+                .collect(),
+            updated_at: epoch_millis(),
+        }
+    }
+
+    fn event(&self, name: &str, version: u16, payload: Value) -> RequestEvent {
+        let mut metadata = match &self.metadata {
+            Value::Object(_) => self.metadata.clone(),
+            _ => json!({}),
+        };
+        metadata["saga"] = json!({ "id": self.id, "name": self.name });
+        RequestEvent {
+            name: String::from(name),
+            version,
+            id: Uuid::new_v4(),
+            flow_id: self.flow_id,
+            payload,
+            identity: self.identity.clone(),
+            auth: self.auth.clone(),
+            metadata,
+        }
+    }
+}
+
+pub trait SagaStore: Send + Sync {
+    fn save(&self, state: &SagaState) -> io::Result<()>;
+
+    fn load(&self, id: &Uuid) -> io::Result<Option<SagaState>>;
+
+    fn unfinished(&self) -> io::Result<Vec<SagaState>>;
+}
+
+#[derive(Default)]
+pub struct InMemorySagaStore {
+    states: Mutex<HashMap<Uuid, SagaState>>,
+}
+
+impl InMemorySagaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SagaStore for InMemorySagaStore {
+    fn save(&self, state: &SagaState) -> io::Result<()> {
+        self.states.lock().unwrap().insert(state.id, state.clone());
+        Ok(())
+    }
+
+    fn load(&self, id: &Uuid) -> io::Result<Option<SagaState>> {
+        Ok(self.states.lock().unwrap().get(id).cloned())
+    }
+
+    fn unfinished(&self) -> io::Result<Vec<SagaState>> {
+        Ok(self
+            .states
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|state| !state.status.is_finished())
+            .cloned()
+            .collect())
+    }
+}
+
+pub struct FileSagaStore {
+    directory: PathBuf,
+}
+
+impl FileSagaStore {
+    pub fn open<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        Ok(FileSagaStore {
+            directory: directory.as_ref().to_path_buf(),
+        })
+    }
+
+    fn path_for(&self, id: &Uuid) -> PathBuf {
+        self.directory.join(format!("{}.json", id))
+    }
+}
+
+impl SagaStore for FileSagaStore {
+    fn save(&self, state: &SagaState) -> io::Result<()> {
+        let path = self.path_for(&state.id);
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec_pretty(state)?)?;
+        fs::rename(temporary, path)
+    }
+
+    fn load(&self, id: &Uuid) -> io::Result<Option<SagaState>> {
+        match fs::read(self.path_for(id)) {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn unfinished(&self) -> io::Result<Vec<SagaState>> {
+        let mut states = vec![];
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let state: SagaState = serde_json::from_slice(&fs::read(path)?)?;
+            if !state.status.is_finished() {
+                states.push(state);
+            }
+        }
+        Ok(states)
+    }
+}
+
+pub struct SagaRunner {
+    store: Box<dyn SagaStore>,
+}
+
+impl SagaRunner {
+    pub fn new(store: Box<dyn SagaStore>) -> Self {
+        SagaRunner { store }
+    }
+
+    pub fn run(&self, client: &dyn EventClient, saga: &Saga) -> io::Result<SagaState> {
+        let state = SagaState::new(saga, Uuid::new_v4(), json!({}), json!({}), json!({}));
+        self.execute(client, state)
+    }
+
+    pub fn run_in_flow(
+        &self,
+        client: &dyn EventClient,
+        saga: &Saga,
+        parent: &RequestEvent,
+    ) -> io::Result<SagaState> {
+        let mut metadata = match &parent.metadata {
+            Value::Object(_) => parent.metadata.clone(),
+            _ => json!({}),
+        };
+        metadata[CALL_DEPTH] = json!(EventContext::detached(parent).depth() + 1);
+        let state = SagaState::new(
+            saga,
+            parent.flow_id,
+            parent.identity.clone(),
+            parent.auth.clone(),
+            metadata,
+        );
+        self.execute(client, state)
+    }
+
+    pub fn resume(&self, client: &dyn EventClient, id: &Uuid) -> io::Result<Option<SagaState>> {
+        match self.store.load(id)? {
+            Some(state) if !state.status.is_finished() => self.compensate(client, state).map(Some),
+            other => Ok(other),
+        }
+    }
+
+    fn execute(&self, client: &dyn EventClient, mut state: SagaState) -> io::Result<SagaState> {
+        self.save(&mut state)?;
+        for idx in 0..state.steps.len() {
+            let step = &state.steps[idx];
+            let event = state.event(&step.name, step.version, step.payload.clone());
+            state.steps[idx].status = StepStatus::InFlight;
+            self.save(&mut state)?;
+            let completed = record(&mut state.steps[idx], client, &event, StepStatus::Completed);
+            self.save(&mut state)?;
+            if !completed {
+                return self.compensate(client, state);
+            }
+        }
+        state.status = SagaStatus::Completed;
+        self.save(&mut state)?;
+        Ok(state)
+    }
+
+    fn compensate(&self, client: &dyn EventClient, mut state: SagaState) -> io::Result<SagaState> {
+        state.status = SagaStatus::Compensating;
+        self.save(&mut state)?;
+        for idx in (0..state.steps.len()).rev() {
+            let step = &state.steps[idx];
+            if !matches!(step.status, StepStatus::Completed | StepStatus::InFlight) {
+                continue;
+            }
+            if let Some(compensation) = &step.compensation {
+                let payload = json!({ "request": step.payload, "response": step.response });
+                let event = state.event(&compensation.name, compensation.version, payload);
+                record(
+                    &mut state.steps[idx],
+                    client,
+                    &event,
+                    StepStatus::Compensated,
+                );
+                self.save(&mut state)?;
+            }
+        }
+        state.status = if state
+            .steps
+            .iter()
+            .any(|step| step.status == StepStatus::CompensationFailed)
+        {
+            SagaStatus::Failed
+        } else {
+            SagaStatus::Compensated
+        };
+        self.save(&mut state)?;
+        Ok(state)
+    }
+
+    fn save(&self, state: &mut SagaState) -> io::Result<()> {
+        state.updated_at = epoch_millis();
+        self.store.save(state)
+    }
+}
+
+fn record(
+    step: &mut StepState,
+    client: &dyn EventClient,
+    event: &RequestEvent,
+    success: StepStatus,
+) -> bool {
+    let failure = match success {
+        StepStatus::Compensated => StepStatus::CompensationFailed,
+        _ => StepStatus::Failed,
+    };
+    match client.send(event) {
+        Ok(response) if !response.is_error() => {
+            if success == StepStatus::Completed {
+                step.response = Some(response.payload);
+            }
+            step.status = success;
+            true
+        }
+        Ok(response) => {
+            let error = error_of(&response);
+            log_failure(event, &error.to_string());
+            step.error = Some(error);
+            step.status = failure;
+            false
+        }
+        Err(err) => {
+            log_failure(event, &format!("{}", err));
+            step.error = Some(json!({ "message": format!("{}", err) }));
+            step.status = failure;
+            false
+        }
+    }
+}
+
+fn error_of(response: &ResponseEvent) -> Value {
+    json!({ "name": response.name, "payload": response.payload })
+}
+
+fn log_failure(event: &RequestEvent, message: &str) {
+    log::warn!(
+        "Saga step {} v{} (flow {}) failed with {}",
+        event.name,
+        event.version,
+        event.flow_id,
+        message
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use crate::errors::EventErrorType;
+    use crate::events::{new_event, response_for};
+    use crate::processor::EventProcessor;
+    use crate::saga::{
+        FileSagaStore, InMemorySagaStore, Saga, SagaRunner, SagaStatus, SagaStep, SagaStore,
+        StepStatus,
+    };
+    use crate::store::SimpleEventStore;
+
+    type Calls = Arc<Mutex<Vec<String>>>;
+
+    fn processor(calls: &Calls, failing: &'static str) -> EventProcessor {
+        let mut store = SimpleEventStore::new();
+        for name in &[
+            "account:debit",
+            "account:refund",
+            "account:credit",
+            "ledger:update",
+            "ledger:revert",
+        ] {
+            let calls = calls.clone();
+            store.add(name, 1, move |req| {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", req.name, req.flow_id));
+                if req.name == failing {
+                    return Err(EventErrorType::generic("UNAVAILABLE", json!({})));
+                }
+                Ok(response_for(req, json!({ "handledBy": req.name })))
+            });
+        }
+        EventProcessor::new(Box::new(store))
+    }
+
+    fn transfer() -> Saga {
+        Saga::new("transfer")
+            .with_step(
+                SagaStep::new("account:debit", 1, json!({ "amount": 10 }))
+                    .with_compensation("account:refund", 1),
+            )
+            .with_step(SagaStep::new("account:credit", 1, json!({ "amount": 10 })))
+            .with_step(
+                SagaStep::new("ledger:update", 1, json!({})).with_compensation("ledger:revert", 1),
+            )
+    }
+
+    #[test]
+    fn test_completes_all_steps_under_one_flow() {
+        let calls = Calls::default();
+        let processor = processor(&calls, "");
+        let runner = SagaRunner::new(Box::new(InMemorySagaStore::new()));
+        let parent = new_event("payment:create", 1, json!({}));
+
+        let state = runner
+            .run_in_flow(&processor, &transfer(), &parent)
+            .unwrap();
+
+        assert_eq!(SagaStatus::Completed, state.status);
+        assert_eq!(parent.flow_id, state.flow_id);
+        assert_eq!(
+            vec![
+                format!("account:debit {}", parent.flow_id),
+                format!("account:credit {}", parent.flow_id),
+                format!("ledger:update {}", parent.flow_id),
+            ],
+            *calls.lock().unwrap()
+        );
+        assert_eq!(
+            Some(json!({ "handledBy": "ledger:update" })),
+            state.steps[2].response
+        );
+    }
+
+    #[test]
+    fn test_compensates_completed_steps_in_reverse_order() {
+        let calls = Calls::default();
+        let processor = processor(&calls, "ledger:update");
+        let store = InMemorySagaStore::new();
+        let runner = SagaRunner::new(Box::new(store));
+
+        let state = runner.run(&processor, &transfer()).unwrap();
+
+        assert_eq!(SagaStatus::Compensated, state.status);
+        let statuses: Vec<StepStatus> = state.steps.iter().map(|step| step.status).collect();
+        assert_eq!(
+            vec![
+                StepStatus::Compensated,
+                StepStatus::Completed,
+                StepStatus::Failed
+            ],
+            statuses
+        );
+        let names: Vec<String> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| call.split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "account:debit",
+                "account:credit",
+                "ledger:update",
+                "account:refund"
+            ],
+            names
+        );
+        assert_eq!(
+            Some(json!("UNAVAILABLE")),
+            state.steps[2]
+                .error
+                .as_ref()
+                .map(|error| error["payload"]["code"].clone())
+        );
+    }
+
+    #[test]
+    fn test_fails_steps_with_malformed_error_responses() {
+        let mut store = SimpleEventStore::new();
+        store.add("account:debit", 1, |req| {
+            let mut response = response_for(req, "boom");
+            response.name = String::from("account:debit:error");
+            Ok(response)
+        });
+        let processor = EventProcessor::new(Box::new(store));
+        let saga = Saga::new("debit").with_step(SagaStep::new("account:debit", 1, ()));
+
+        let state = SagaRunner::new(Box::new(InMemorySagaStore::new()))
+            .run(&processor, &saga)
+            .unwrap();
+
+        assert_eq!(SagaStatus::Compensated, state.status);
+        assert_eq!(StepStatus::Failed, state.steps[0].status);
+        assert_eq!(
+            Some(json!({ "name": "account:debit:error", "payload": "boom" })),
+            state.steps[0].error
+        );
+    }
+
+    #[test]
+    fn test_reports_failed_compensations() {
+        let calls = Calls::default();
+        let processor = processor(&calls, "account:refund");
+        let saga = Saga::new("transfer")
+            .with_step(SagaStep::new("account:debit", 1, ()).with_compensation("account:refund", 1))
+            .with_step(SagaStep::new("missing:event", 1, ()));
+
+        let state = SagaRunner::new(Box::new(InMemorySagaStore::new()))
+            .run(&processor, &saga)
+            .unwrap();
+
+        assert_eq!(SagaStatus::Failed, state.status);
+        assert_eq!(StepStatus::CompensationFailed, state.steps[0].status);
+        assert_eq!(StepStatus::Failed, state.steps[1].status);
+    }
+
+    #[test]
+    fn test_file_store_persists_and_resumes_unfinished_sagas() {
+        let directory = env::temp_dir().join(format!("sagas-{}", uuid::Uuid::new_v4()));
+        let store = FileSagaStore::open(&directory).unwrap();
+        let calls = Calls::default();
+        let processor = processor(&calls, "");
+
+        let mut state = SagaRunner::new(Box::new(InMemorySagaStore::new()))
+            .run(&processor, &transfer())
+            .unwrap();
+        state.status = SagaStatus::Running;
+        state.steps[2].status = StepStatus::Pending;
+        store.save(&state).unwrap();
+
+        assert_eq!(Some(state.clone()), store.load(&state.id).unwrap());
+        assert_eq!(1, store.unfinished().unwrap().len());
+
+        let runner = SagaRunner::new(Box::new(FileSagaStore::open(&directory).unwrap()));
+        let resumed = runner.resume(&processor, &state.id).unwrap().unwrap();
+
+        assert_eq!(SagaStatus::Compensated, resumed.status);
+        assert!(calls
+            .lock()
+            .unwrap()
+            .last()
+            .unwrap()
+            .starts_with("account:refund"));
+        assert!(store.unfinished().unwrap().is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_resume_compensates_steps_left_in_flight() {
+        let calls = Calls::default();
+        let processor = processor(&calls, "");
+        let mut state = SagaRunner::new(Box::new(InMemorySagaStore::new()))
+            .run(&processor, &transfer())
+            .unwrap();
+        state.status = SagaStatus::Running;
+        state.steps[2].status = StepStatus::InFlight;
+        state.steps[2].response = None;
+        let store = InMemorySagaStore::new();
+        store.save(&state).unwrap();
+        calls.lock().unwrap().clear();
+
+        let resumed = SagaRunner::new(Box::new(store))
+            .resume(&processor, &state.id)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(SagaStatus::Compensated, resumed.status);
+        let statuses: Vec<StepStatus> = resumed.steps.iter().map(|step| step.status).collect();
+        assert_eq!(
+            vec![
+                StepStatus::Compensated,
+                StepStatus::Completed,
+                StepStatus::Compensated
+            ],
+            statuses
+        );
+        let names: Vec<String> = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|call| call.split(' ').next().unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["ledger:revert", "account:refund"], names);
+    }
+
+    #[test]
+    fn test_steps_inherit_call_depth_and_deadline_of_the_parent() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut store = SimpleEventStore::new();
+        {
+            let seen = seen.clone();
+            store.add("account:debit", 1, move |req| {
+                seen.lock().unwrap().push(req.metadata.clone());
+                Ok(response_for(req, json!({})))
+            });
+        }
+        let processor = EventProcessor::new(Box::new(store)).with_max_call_depth(3);
+        let runner = SagaRunner::new(Box::new(InMemorySagaStore::new()));
+        let saga = Saga::new("debit").with_step(SagaStep::new("account:debit", 1, ()));
+        let mut parent = new_event("payment:create", 1, json!({}));
+        parent.metadata = json!({ "callDepth": 2, "deadline": u64::MAX, "traceId": "t-1" });
+
+        let state = runner.run_in_flow(&processor, &saga, &parent).unwrap();
+
+        assert_eq!(SagaStatus::Completed, state.status);
+        let metadata = seen.lock().unwrap()[0].clone();
+        assert_eq!(3, metadata["callDepth"]);
+        assert_eq!(u64::MAX, metadata["deadline"]);
+        assert_eq!("t-1", metadata["traceId"]);
+        assert_eq!("debit", metadata["saga"]["name"]);
+
+        parent.metadata["callDepth"] = json!(3);
+        let state = runner.run_in_flow(&processor, &saga, &parent).unwrap();
+        assert_eq!(SagaStatus::Compensated, state.status);
+        assert_eq!(
+            Some(json!("CALL_DEPTH_EXCEEDED")),
+            state.steps[0]
+                .error
+                .as_ref()
+                .map(|error| error["payload"]["code"].clone())
+        );
+        assert_eq!(1, seen.lock().unwrap().len());
+    }
+}
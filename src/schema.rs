@@ -0,0 +1,216 @@
+use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "json-schema")]
+use std::sync::Arc;
+
+#[cfg(feature = "json-schema")]
+use jsonschema::Validator;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::errors::EventErrorType;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidSchema {
+    pub message: String,
+}
+
+impl Display for InvalidSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid schema: {}", self.message)
+    }
+}
+
+impl std::error::Error for InvalidSchema {}
+
+#[derive(Clone)]
+pub struct PayloadSchema {
+    schema: Value,
+    #[cfg(feature = "json-schema")]
+    validator: Arc<Validator>,
+}
+
+impl PayloadSchema {
+    #[cfg(feature = "json-schema")]
+    pub fn new(schema: Value) -> Result<Self, InvalidSchema> {
+        let validator = jsonschema::validator_for(&schema).map_err(|err| InvalidSchema {
+            message: format!("{}", err),
+        })?;
+        Ok(PayloadSchema {
+            schema,
+            validator: Arc::new(validator),
+        })
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    pub fn new(schema: Value) -> Result<Self, InvalidSchema> {
+        if !schema.is_object() && !schema.is_boolean() {
+            return Err(InvalidSchema {
+                message: format!("{} is not an object or a boolean", schema),
+            });
+        }
+        Ok(PayloadSchema { schema })
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    #[cfg(feature = "json-schema")]
+    pub fn validate(&self, payload: &Value) -> Vec<Violation> {
+        self.validator
+            .iter_errors(payload)
+            .map(|err| Violation {
+                pointer: err.instance_path.as_str().to_string(),
+                message: format!("{}", err),
+            })
+            .collect()
+    }
+}
+
+impl Debug for PayloadSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PayloadSchema").field(&self.schema).finish()
+    }
+}
+
+pub fn invalid_payload(violations: &[Violation]) -> EventErrorType {
+    EventErrorType::bad_request("INVALID_PAYLOAD", json!({ "violations": violations }))
+}
+
+pub fn invalid_response_payload(violations: &[Violation]) -> EventErrorType {
+    EventErrorType::generic(
+        "INVALID_RESPONSE_PAYLOAD",
+        json!({ "violations": violations }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::events::response_for;
+    #[cfg(feature = "json-schema")]
+    use crate::processor::EventProcessor;
+    use crate::schema::PayloadSchema;
+    use crate::store::{EventStore, SimpleEventStore};
+
+    #[cfg(feature = "json-schema")]
+    fn account_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 }
+            }
+        })
+    }
+
+    #[cfg(feature = "json-schema")]
+    fn raw_event(payload: &str) -> String {
+        format!(
+            r#"{{
+                    "name": "account:get",
+                    "version": 1,
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {{}},
+                    "identity": {{}},
+                    "auth": {{}}
+                }}"#,
+            payload
+        )
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_reports_violations_with_json_pointers() {
+        let schema = PayloadSchema::new(account_schema()).unwrap();
+
+        assert!(schema.validate(&json!({"id": 1})).is_empty());
+
+        let violations = schema.validate(&json!({"id": 0}));
+        assert_eq!(1, violations.len());
+        assert_eq!("/id", violations[0].pointer);
+    }
+
+    #[test]
+    fn test_rejects_invalid_schema() {
+        assert!(PayloadSchema::new(json!(12)).is_err());
+        #[cfg(feature = "json-schema")]
+        assert!(PayloadSchema::new(json!({"type": 12})).is_err());
+
+        let mut store = SimpleEventStore::new();
+        let error = store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .request_schema(json!("object"))
+            .unwrap_err();
+        assert!(error.message.contains("account:get v1"));
+        assert!(store
+            .definition_for("account:get", 1)
+            .unwrap()
+            .request_schema
+            .is_none());
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_rejects_requests_violating_schema() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| Ok(response_for(req, "ok")))
+            .request_schema(account_schema())
+            .unwrap();
+        let event_processor = EventProcessor::new(Box::new(store));
+
+        assert!(event_processor
+            .process_event(&raw_event(r#"{"id": 42}"#))
+            .is_success());
+
+        let response_event = event_processor.process_event(&raw_event(r#"{"id": "42"}"#));
+
+        assert_eq!("account:get:badRequest", response_event.name);
+        assert_eq!("INVALID_PAYLOAD", response_event.payload["code"]);
+        assert_eq!(
+            "/id",
+            response_event.payload["parameters"]["violations"][0]["pointer"]
+        );
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_validates_responses_when_enabled() {
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| {
+                Ok(response_for(req, json!({"id": "x"})))
+            })
+            .response_schema(account_schema())
+            .unwrap();
+
+        let event_processor = EventProcessor::new(Box::new(store));
+        assert!(event_processor
+            .process_event(&raw_event(r#"{"id": 42}"#))
+            .is_success());
+
+        let mut store = SimpleEventStore::new();
+        store
+            .add("account:get", 1, |req| {
+                Ok(response_for(req, json!({"id": "x"})))
+            })
+            .response_schema(account_schema())
+            .unwrap();
+
+        let event_processor = EventProcessor::new(Box::new(store)).with_response_validation(true);
+        let response_event = event_processor.process_event(&raw_event(r#"{"id": 42}"#));
+
+        assert_eq!("account:get:error", response_event.name);
+        assert_eq!("INVALID_RESPONSE_PAYLOAD", response_event.payload["code"]);
+    }
+}
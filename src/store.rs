@@ -1,56 +1,297 @@
 use std::collections::HashMap;
 
+use crate::context::EventContext;
+use crate::definition::EventDefinition;
 use crate::errors::EventErrorType;
 use crate::events::{RequestEvent, ResponseEvent};
-use crate::handlers::{EventHandler, FnForwardHandler};
+use crate::handlers::{
+    EventHandler, EventListener, FnContextHandler, FnForwardHandler, FnListener, NamedEventHandler,
+    TypedHandler,
+};
+use crate::migration::Migration;
+#[cfg(feature = "schemars")]
+use crate::schema::InvalidSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::ops::Deref;
 
 pub trait EventStore {
     fn handler_for(&self, event_name: &str, version: u16) -> Option<&dyn EventHandler>;
+
+    fn definition_for(&self, _event_name: &str, _version: u16) -> Option<&EventDefinition> {
+        None
+    }
+
+    fn definitions(&self) -> Vec<&EventDefinition> {
+        vec![]
+    }
+
+    fn listeners_for(&self, _event_name: &str, _version: u16) -> Vec<&dyn EventListener> {
+        vec![]
+    }
+
+    fn migration_for(&self, _event_name: &str, _from_version: u16) -> Option<&Migration> {
+        None
+    }
+}
+
+pub trait IntoBoxedHandler<H: ?Sized> {
+    fn into_boxed(self) -> Box<H>;
 }
 
-pub struct SimpleEventStore<'a> {
-    handlers: HashMap<(String, u16), Box<dyn EventHandler + 'a>>,
+impl<'a, T: EventHandler + 'a> IntoBoxedHandler<dyn EventHandler + 'a> for T {
+    fn into_boxed(self) -> Box<dyn EventHandler + 'a> {
+        Box::new(self)
+    }
+}
+
+impl<'a, T: EventHandler + Send + Sync + 'a> IntoBoxedHandler<dyn EventHandler + Send + Sync + 'a>
+    for T
+{
+    fn into_boxed(self) -> Box<dyn EventHandler + Send + Sync + 'a> {
+        Box::new(self)
+    }
+}
+
+pub type SyncEventStore<'a> = SimpleEventStore<'a, dyn EventHandler + Send + Sync + 'a>;
+
+pub struct SimpleEventStore<'a, H: ?Sized + 'a = dyn EventHandler + 'a> {
+    handlers: HashMap<(String, u16), Box<H>>,
+    definitions: HashMap<(String, u16), EventDefinition>,
+    listeners: HashMap<(String, u16), Vec<Box<dyn EventListener + 'a>>>,
+    migrations: HashMap<(String, u16), Migration>,
 }
 
 impl<'a> SimpleEventStore<'a> {
     pub fn new() -> Self {
+        SimpleEventStore::empty()
+    }
+}
+
+impl<'a> SyncEventStore<'a> {
+    pub fn new_sync() -> Self {
+        SimpleEventStore::empty()
+    }
+}
+
+impl<'a, H: ?Sized + 'a> SimpleEventStore<'a, H> {
+    fn empty() -> Self {
         SimpleEventStore {
             handlers: HashMap::new(),
+            definitions: HashMap::new(),
+            listeners: HashMap::new(),
+            migrations: HashMap::new(),
         }
     }
 
-    pub fn add<T>(&mut self, name: &str, version: u16, handler: T)
+    pub fn add<T>(&mut self, name: &str, version: u16, handler: T) -> &mut EventDefinition
     where
         T: Fn(&RequestEvent) -> Result<ResponseEvent, EventErrorType> + 'a,
+        FnForwardHandler<T>: IntoBoxedHandler<H>,
     {
-        self.handlers.insert(
-            (String::from(name), version),
-            Box::new(FnForwardHandler::new(handler)),
-        );
+        self.add_handler(name, version, FnForwardHandler::new(handler))
+    }
+
+    pub fn add_with_context<T>(
+        &mut self,
+        name: &str,
+        version: u16,
+        handler: T,
+    ) -> &mut EventDefinition
+    where
+        T: Fn(&EventContext, &RequestEvent) -> Result<ResponseEvent, EventErrorType> + 'a,
+        FnContextHandler<T>: IntoBoxedHandler<H>,
+    {
+        self.add_handler(name, version, FnContextHandler::new(handler))
+    }
+
+    pub fn add_typed<Req, Resp, T>(
+        &mut self,
+        name: &str,
+        version: u16,
+        handler: T,
+    ) -> &mut EventDefinition
+    where
+        Req: DeserializeOwned + 'a,
+        Resp: Serialize + 'a,
+        T: Fn(&RequestEvent, Req) -> Result<Resp, EventErrorType> + 'a,
+        TypedHandler<Req, Resp, T>: IntoBoxedHandler<H>,
+    {
+        self.add_handler(name, version, TypedHandler::new(handler))
+    }
+
+    #[cfg(feature = "schemars")]
+    pub fn add_typed_with_schemas<Req, Resp, T>(
+        &mut self,
+        name: &str,
+        version: u16,
+        handler: T,
+    ) -> Result<&mut EventDefinition, InvalidSchema>
+    where
+        Req: DeserializeOwned + schemars::JsonSchema + 'a,
+        Resp: Serialize + schemars::JsonSchema + 'a,
+        T: Fn(&RequestEvent, Req) -> Result<Resp, EventErrorType> + 'a,
+        TypedHandler<Req, Resp, T>: IntoBoxedHandler<H>,
+    {
+        self.add_typed(name, version, handler)
+            .schemas_from::<Req, Resp>()
+    }
+
+    pub fn add_handler<T>(&mut self, name: &str, version: u16, handler: T) -> &mut EventDefinition
+    where
+        T: IntoBoxedHandler<H>,
+    {
+        let key = (String::from(name), version);
+        self.handlers.insert(key.clone(), handler.into_boxed());
+
+        self.definitions
+            .insert(key.clone(), EventDefinition::new(name, version));
+        self.definitions.get_mut(&key).unwrap()
     }
+
+    pub fn add_listener<T>(&mut self, name: &str, version: u16, listener: T)
+    where
+        T: Fn(&RequestEvent) -> Result<(), EventErrorType> + Send + Sync + 'a,
+    {
+        self.subscribe(name, version, FnListener::new(listener))
+    }
+
+    pub fn subscribe<L>(&mut self, name: &str, version: u16, listener: L)
+    where
+        L: EventListener + 'a,
+    {
+        self.listeners
+            .entry((String::from(name), version))
+            .or_default()
+            .push(Box::new(listener));
+    }
+
+    pub fn add_migration(
+        &mut self,
+        name: &str,
+        from_version: u16,
+        migration: Migration,
+    ) -> &mut EventDefinition {
+        let key = (String::from(name), from_version);
+        self.migrations.insert(key.clone(), migration);
+
+        self.definitions
+            .entry(key)
+            .or_insert_with(|| EventDefinition::new(name, from_version))
+    }
+
+    pub fn register<T>(&mut self, handler: T) -> &mut EventDefinition
+    where
+        T: NamedEventHandler + IntoBoxedHandler<H>,
+    {
+        self.add_handler(T::NAME, T::VERSION, handler)
+    }
+}
+
+#[macro_export]
+macro_rules! event_store {
+    ($($handler:expr),* $(,)?) => {{
+        let mut store = $crate::store::SimpleEventStore::new();
+        $(store.register($handler);)*
+        store
+    }};
 }
 
-impl<'a> EventStore for SimpleEventStore<'a> {
+impl<'a, H: ?Sized + EventHandler + 'a> EventStore for SimpleEventStore<'a, H> {
     fn handler_for(&self, event_name: &str, version: u16) -> Option<&dyn EventHandler> {
         match self.handlers.get(&(String::from(event_name), version)) {
-            Some(handler) => Some(handler.deref()),
+            Some(handler) => Some(handler),
             None => None,
         }
     }
+
+    fn definition_for(&self, event_name: &str, version: u16) -> Option<&EventDefinition> {
+        self.definitions.get(&(String::from(event_name), version))
+    }
+
+    fn definitions(&self) -> Vec<&EventDefinition> {
+        let mut definitions: Vec<&EventDefinition> = self.definitions.values().collect();
+        definitions.sort_by(|a, b| (&a.name, a.version).cmp(&(&b.name, b.version)));
+        definitions
+    }
+
+    fn listeners_for(&self, event_name: &str, version: u16) -> Vec<&dyn EventListener> {
+        match self.listeners.get(&(String::from(event_name), version)) {
+            Some(listeners) => listeners.iter().map(|listener| listener.deref()).collect(),
+            None => vec![],
+        }
+    }
+
+    fn migration_for(&self, event_name: &str, from_version: u16) -> Option<&Migration> {
+        self.migrations
+            .get(&(String::from(event_name), from_version))
+    }
 }
 
-impl<'a> Default for SimpleEventStore<'a> {
+impl<'a, H: ?Sized + 'a> Default for SimpleEventStore<'a, H> {
     fn default() -> Self {
-        Self::new()
+        Self::empty()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::events::{parse_event, response_for};
+    use events_protocol_macros::event_handler;
+    use serde::{Deserialize, Serialize};
+
+    use crate::errors::EventError;
+    use crate::errors::EventErrorType::{self, NotFound};
+    use crate::events::{parse_event, response_for, RequestEvent};
+    use crate::handlers::NamedEventHandler;
     use crate::store::{EventStore, SimpleEventStore};
 
+    #[derive(Deserialize)]
+    struct GetAccount {
+        id: u64,
+    }
+
+    #[derive(Serialize)]
+    struct Account {
+        id: u64,
+        owner: String,
+    }
+
+    #[event_handler(name = "account:get", version = 1)]
+    fn get_account(payload: GetAccount) -> Result<Account, EventErrorType> {
+        if payload.id != 42 {
+            return Err(NotFound(EventError::new(
+                "ACCOUNT_NOT_FOUND",
+                serde_json::json!({ "id": payload.id }),
+            )));
+        }
+        Ok(Account {
+            id: payload.id,
+            owner: String::from("bruno"),
+        })
+    }
+
+    #[event_handler(name = "account:whoami", version = 2)]
+    fn who_am_i(#[event] event: &RequestEvent) -> Result<String, EventErrorType> {
+        Ok(event.identity["userId"].to_string())
+    }
+
+    fn account_event(name: &str, version: u16, payload: &str) -> RequestEvent {
+        parse_event(&format!(
+            r#"{{
+                    "name": "{}",
+                    "version": {},
+                    "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                    "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                    "payload": {},
+                    "metadata": {{}},
+                    "identity": {{ "userId": 7 }},
+                    "auth": {{}}
+                }}"#,
+            name, version, payload
+        ))
+        .unwrap()
+    }
+
     #[test]
     fn test_can_add_event_handler() {
         let mut store = SimpleEventStore::new();
@@ -91,4 +332,97 @@ mod tests {
         let option = store.handler_for("event:test", 1);
         assert!(option.is_none());
     }
+
+    #[test]
+    fn test_can_register_annotated_event_handlers() {
+        assert_eq!("account:get", GetAccountHandler::NAME);
+        assert_eq!(1, GetAccountHandler::VERSION);
+        assert_eq!("bruno", get_account(GetAccount { id: 42 }).unwrap().owner);
+
+        let store = event_store![GetAccountHandler, WhoAmIHandler];
+
+        let handler = store.handler_for("account:get", 1).unwrap();
+        let response = handler
+            .handle(&account_event("account:get", 1, r#"{"id": 42}"#))
+            .unwrap();
+        assert_eq!("bruno", response.payload["owner"]);
+
+        let error = handler
+            .handle(&account_event("account:get", 1, r#"{"id": 1}"#))
+            .unwrap_err();
+        assert_eq!("ACCOUNT_NOT_FOUND", error.value().code);
+
+        let error = handler
+            .handle(&account_event("account:get", 1, r#"{"name": "x"}"#))
+            .unwrap_err();
+        assert_eq!("badRequest", error.error_type());
+        assert_eq!("INVALID_PAYLOAD", error.value().code);
+
+        let handler = store.handler_for("account:whoami", 2).unwrap();
+        let response = handler
+            .handle(&account_event("account:whoami", 2, "{}"))
+            .unwrap();
+        assert_eq!("7", response.payload);
+    }
+
+    #[test]
+    fn test_can_add_typed_event_handler() {
+        let mut store = SimpleEventStore::new();
+        store.add_typed("account:get", 1, |_event, payload: GetAccount| {
+            Ok(Account {
+                id: payload.id,
+                owner: String::from("bruno"),
+            })
+        });
+
+        let handler = store.handler_for("account:get", 1).unwrap();
+        let response = handler
+            .handle(&account_event("account:get", 1, r#"{"id": 42}"#))
+            .unwrap();
+        assert_eq!(42, response.payload["id"]);
+
+        let error = handler
+            .handle(&account_event("account:get", 1, r#"{"id": "x"}"#))
+            .unwrap_err();
+        assert_eq!("INVALID_PAYLOAD", error.code());
+    }
+
+    #[cfg(all(feature = "schemars", feature = "json-schema"))]
+    #[test]
+    fn test_records_schemas_for_typed_event_handler() {
+        #[derive(Deserialize, schemars::JsonSchema)]
+        struct Transfer {
+            amount: u64,
+        }
+
+        #[derive(Serialize, schemars::JsonSchema)]
+        struct Receipt {
+            receipt_id: String,
+        }
+
+        let mut store = SimpleEventStore::new();
+        store
+            .add_typed_with_schemas("transfer:create", 1, |_event, payload: Transfer| {
+                Ok(Receipt {
+                    receipt_id: format!("r-{}", payload.amount),
+                })
+            })
+            .unwrap();
+
+        let definition = store.definition_for("transfer:create", 1).unwrap();
+        let request_schema = definition.request_schema.as_ref().unwrap();
+        assert_eq!("Transfer", request_schema.schema()["title"]);
+        assert!(request_schema
+            .validate(&serde_json::json!({"amount": 10}))
+            .is_empty());
+        assert!(!request_schema
+            .validate(&serde_json::json!({"amount": "ten"}))
+            .is_empty());
+
+        let response_schema = definition.response_schema.as_ref().unwrap();
+        assert_eq!(
+            serde_json::json!(["receipt_id"]),
+            response_schema.schema()["required"]
+        );
+    }
 }
@@ -0,0 +1,22 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub mod framed;
+pub mod stdio;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new(shutdown: Arc<AtomicBool>) -> Self {
+        ShutdownHandle { shutdown }
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::errors::message_too_large;
+use crate::processor::SyncEventProcessor;
+use crate::transport::ShutdownHandle;
+
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 1024 * 1024;
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub trait Connection: Read + Write + Send + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        TcpStream::shutdown(self, how)
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        UnixStream::shutdown(self, how)
+    }
+}
+
+pub trait Listener {
+    type Connection: Connection;
+
+    fn accept(&self) -> io::Result<Self::Connection>;
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Listener for TcpListener {
+    type Connection = TcpStream;
+
+    fn accept(&self) -> io::Result<TcpStream> {
+        let (stream, _) = TcpListener::accept(self)?;
+        stream.set_nonblocking(false)?;
+        Ok(stream)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpListener::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    type Connection = UnixStream;
+
+    fn accept(&self) -> io::Result<UnixStream> {
+        let (stream, _) = UnixListener::accept(self)?;
+        stream.set_nonblocking(false)?;
+        Ok(stream)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixListener::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Frame {
+    Payload(Vec<u8>),
+    TooLarge(usize),
+}
+
+pub fn read_frame<R: Read>(reader: &mut R, max_frame_bytes: usize) -> io::Result<Option<Frame>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed inside a frame header",
+                ))
+            }
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let length = u32::from_be_bytes(header) as usize;
+    if length > max_frame_bytes {
+        io::copy(&mut reader.take(length as u64), &mut io::sink())?;
+        return Ok(Some(Frame::TooLarge(length)));
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(Frame::Payload(payload)))
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > u32::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "frame exceeds the 4 GiB length prefix",
+        ));
+    }
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+#[derive(Debug)]
+pub struct FramedServer {
+    max_frame_bytes: usize,
+    concurrency: usize,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl FramedServer {
+    pub fn new() -> Self {
+        FramedServer {
+            max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+            concurrency: 8,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_max_frame_bytes(mut self, max_frame_bytes: usize) -> Self {
+        self.max_frame_bytes = max_frame_bytes;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(self.shutdown.clone())
+    }
+
+    pub fn serve<L: Listener>(
+        &self,
+        processor: &SyncEventProcessor,
+        listener: L,
+    ) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+        let connections = Mutex::new(HashMap::new());
+
+        thread::scope(|scope| {
+            let mut next_id = 0u64;
+            let result = loop {
+                if self.shutdown.load(Ordering::SeqCst) {
+                    break Ok(());
+                }
+                match listener.accept() {
+                    Ok(stream) => {
+                        let id = next_id;
+                        next_id += 1;
+                        match stream.try_clone() {
+                            Ok(control) => {
+                                connections.lock().unwrap().insert(id, control);
+                            }
+                            Err(err) => {
+                                log::warn!("Could not track framed connection: {}", err);
+                                continue;
+                            }
+                        }
+                        let connections = &connections;
+                        scope.spawn(move || {
+                            if let Err(err) = self.serve_connection(processor, stream) {
+                                log::warn!("Framed connection closed with error: {}", err);
+                            }
+                            connections.lock().unwrap().remove(&id);
+                        });
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL)
+                    }
+                    Err(err) => break Err(err),
+                }
+            };
+
+            for stream in connections.lock().unwrap().values() {
+                let _ = stream.shutdown(Shutdown::Read);
+            }
+            result
+        })
+    }
+
+    fn serve_connection<C: Connection>(
+        &self,
+        processor: &SyncEventProcessor,
+        stream: C,
+    ) -> io::Result<()> {
+        let writer = Mutex::new(stream.try_clone()?);
+        let mut reader = BufReader::new(stream);
+        let (jobs, queue) = mpsc::sync_channel::<Frame>(self.concurrency);
+        let queue = Mutex::new(queue);
+
+        let result = thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                let (queue, writer) = (&queue, &writer);
+                scope.spawn(move || loop {
+                    let frame = match queue.lock().unwrap().recv() {
+                        Ok(frame) => frame,
+                        Err(_) => break,
+                    };
+                    let response = match frame {
+                        Frame::Payload(payload) => {
+                            processor.process_event(&String::from_utf8_lossy(&payload))
+                        }
+                        Frame::TooLarge(_) => message_too_large(self.max_frame_bytes),
+                    };
+                    let payload = serde_json::to_vec(&response).unwrap();
+                    let mut writer = writer.lock().unwrap();
+                    if let Err(err) = write_frame(&mut *writer, &payload) {
+                        log::warn!("Could not write response {}: {}", response.id, err);
+                        let _ = writer.shutdown(Shutdown::Both);
+                        break;
+                    }
+                });
+            }
+
+            let result = loop {
+                match read_frame(&mut reader, self.max_frame_bytes) {
+                    // The id of an oversized frame is unknown, so the connection is closed
+                    // once the requests read before it have been answered.
+                    Ok(Some(Frame::TooLarge(length))) => {
+                        log::warn!(
+                            "Closing framed connection after a frame of {} bytes",
+                            length
+                        );
+                        let _ = jobs.send(Frame::TooLarge(length));
+                        break Ok(());
+                    }
+                    Ok(Some(frame)) => {
+                        if jobs.send(frame).is_err() {
+                            break Ok(());
+                        }
+                    }
+                    Ok(None) => break Ok(()),
+                    Err(err) => break Err(err),
+                }
+            };
+            drop(jobs);
+            result
+        });
+
+        let _ = writer.lock().unwrap().shutdown(Shutdown::Write);
+        result
+    }
+}
+
+impl Default for FramedServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use uuid::Uuid;
+
+    use crate::client::framed::FramedEventClient;
+    use crate::client::{ClientError, EventClient};
+    use crate::events::{parse_event, response_for, RequestEvent, ResponseEvent};
+    use crate::processor::{EventProcessor, SyncEventProcessor};
+    use crate::store::SimpleEventStore;
+    use crate::transport::framed::{read_frame, write_frame, Frame, FramedServer};
+
+    fn processor() -> SyncEventProcessor {
+        let mut store = SimpleEventStore::new_sync();
+        store.add("event:test", 1, |req| {
+            let delay = req.payload["delay"].as_u64().unwrap_or(0);
+            thread::sleep(Duration::from_millis(delay));
+            Ok(response_for(req, delay))
+        });
+        EventProcessor::new_sync(Box::new(store))
+    }
+
+    fn event(delay: u64) -> RequestEvent {
+        let mut event = parse_event(
+            r#"{
+                "name": "event:test",
+                "version": 1,
+                "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                "payload": {},
+                "metadata": {},
+                "identity": {},
+                "auth": {}
+            }"#,
+        )
+        .unwrap();
+        event.id = Uuid::new_v4();
+        event.payload = serde_json::json!({ "delay": delay });
+        event
+    }
+
+    #[test]
+    fn test_reads_and_skips_frames() {
+        let mut buffer = vec![];
+        write_frame(&mut buffer, b"{}").unwrap();
+        write_frame(&mut buffer, &[b' '; 64]).unwrap();
+        write_frame(&mut buffer, b"[]").unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(
+            Some(Frame::Payload(b"{}".to_vec())),
+            read_frame(&mut reader, 16).unwrap()
+        );
+        assert_eq!(
+            Some(Frame::TooLarge(64)),
+            read_frame(&mut reader, 16).unwrap()
+        );
+        assert_eq!(
+            Some(Frame::Payload(b"[]".to_vec())),
+            read_frame(&mut reader, 16).unwrap()
+        );
+        assert_eq!(None, read_frame(&mut reader, 16).unwrap());
+    }
+
+    #[test]
+    fn test_multiplexes_requests_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = FramedServer::new().with_concurrency(4);
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let client = Arc::new(FramedEventClient::connect_tcp(address).unwrap());
+            let senders: Vec<_> = [60, 10, 30]
+                .iter()
+                .map(|delay| {
+                    let client = client.clone();
+                    let event = event(*delay);
+                    scope.spawn(move || (event.id, *delay, client.send(&event).unwrap()))
+                })
+                .collect();
+            for sender in senders {
+                let (id, delay, response) = sender.join().unwrap();
+                assert_eq!(id, response.id);
+                assert_eq!(delay, response.payload);
+            }
+
+            drop(client);
+            shutdown.shutdown();
+        });
+    }
+
+    #[test]
+    fn test_closes_the_connection_after_an_oversized_frame() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = FramedServer::new().with_max_frame_bytes(512);
+        let shutdown = server.shutdown_handle();
+        let (started, running) = mpsc::channel();
+        let started = Mutex::new(started);
+        let mut store = SimpleEventStore::new_sync();
+        store.add("event:test", 1, move |req| {
+            started.lock().unwrap().send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            Ok(response_for(req, "done"))
+        });
+        let processor = EventProcessor::new_sync(Box::new(store));
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let client = Arc::new(
+                FramedEventClient::connect_tcp(address)
+                    .unwrap()
+                    .with_timeout(Duration::from_secs(10)),
+            );
+            let running_request = {
+                let client = client.clone();
+                scope.spawn(move || client.send(&event(0)))
+            };
+            running.recv().unwrap();
+
+            let mut large = event(0);
+            large.payload = serde_json::json!({ "padding": "x".repeat(1024) });
+            match client.send(&large) {
+                Err(ClientError::Transport(message)) => {
+                    assert!(message.contains("MESSAGE_TOO_LARGE"), "{}", message)
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+            assert_eq!("done", running_request.join().unwrap().unwrap().payload);
+            assert!(client.send(&event(0)).is_err());
+
+            drop(client);
+            shutdown.shutdown();
+        });
+    }
+
+    #[test]
+    fn test_answers_invalid_events_with_their_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = FramedServer::new();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let mut stream = TcpStream::connect(address).unwrap();
+            let id = Uuid::new_v4();
+            let invalid = serde_json::json!({ "id": id, "name": 7 });
+            write_frame(&mut stream, invalid.to_string().as_bytes()).unwrap();
+
+            let response = match read_frame(&mut stream, 4096).unwrap() {
+                Some(Frame::Payload(payload)) => serde_json::from_slice::<ResponseEvent>(&payload),
+                other => panic!("unexpected frame: {:?}", other),
+            }
+            .unwrap();
+            assert_eq!("badProtocol", response.name);
+            assert_eq!(id, response.id);
+
+            drop(stream);
+            shutdown.shutdown();
+        });
+    }
+
+    #[test]
+    fn test_shutdown_closes_open_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = FramedServer::new();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            let serving = scope.spawn(|| server.serve(&processor, listener));
+            let client = FramedEventClient::connect_tcp(address).unwrap();
+            assert!(client.send(&event(0)).is_ok());
+
+            shutdown.shutdown();
+            serving.join().unwrap().unwrap();
+
+            assert!(client.send(&event(0)).is_err());
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_serves_unix_sockets() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("framed-{}.sock", Uuid::new_v4()));
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = FramedServer::new();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let client = FramedEventClient::connect_unix(&path).unwrap();
+            let event = event(0);
+            let response = client.send(&event).unwrap();
+            assert_eq!(event.id, response.id);
+
+            drop(client);
+            shutdown.shutdown();
+        });
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use crate::errors::message_too_large;
+use crate::events::ResponseEvent;
+use crate::processor::{EventProcessor, ProcessorStore, SyncEventProcessor};
+
+const DEFAULT_MAX_LINE_BYTES: usize = 1024 * 1024;
+const DEFAULT_MAX_IN_FLIGHT: usize = 64;
+
+enum Line {
+    Event(String),
+    TooLarge,
+}
+
+#[derive(Debug, Clone)]
+pub struct StdioTransport {
+    max_line_bytes: usize,
+    concurrency: usize,
+    max_in_flight: usize,
+}
+
+impl StdioTransport {
+    pub fn new() -> Self {
+        StdioTransport {
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+            concurrency: 1,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+        }
+    }
+
+    pub fn with_max_line_bytes(mut self, max_line_bytes: usize) -> Self {
+        self.max_line_bytes = max_line_bytes;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    pub fn serve_stdio<S>(&self, processor: &EventProcessor<S>) -> io::Result<()>
+    where
+        S: ?Sized + ProcessorStore,
+    {
+        let stdin = io::stdin();
+        self.serve(processor, stdin.lock(), io::stdout())
+    }
+
+    pub fn serve<S, R, W>(
+        &self,
+        processor: &EventProcessor<S>,
+        reader: R,
+        writer: W,
+    ) -> io::Result<()>
+    where
+        S: ?Sized + ProcessorStore,
+        R: BufRead,
+        W: Write + Send,
+    {
+        if self.concurrency == 1 {
+            return self.serve_sequential(processor, reader, writer);
+        }
+        match processor.shared() {
+            Some(processor) => self.serve_concurrent(processor, reader, writer),
+            None => {
+                log::warn!(
+                    "Stdio concurrency {} needs a processor created with EventProcessor::new_sync, events will run sequentially",
+                    self.concurrency
+                );
+                self.serve_sequential(processor, reader, writer)
+            }
+        }
+    }
+
+    fn serve_sequential<S: ?Sized + ProcessorStore, R: BufRead, W: Write>(
+        &self,
+        processor: &EventProcessor<S>,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        while let Some(line) = self.read_line(&mut reader)? {
+            write_response(&mut writer, &self.respond(processor, line))?;
+        }
+        Ok(())
+    }
+
+    fn serve_concurrent<R: BufRead, W: Write + Send>(
+        &self,
+        processor: &SyncEventProcessor,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let (jobs, queue) = mpsc::sync_channel::<(u64, Line)>(self.concurrency);
+        let queue = Mutex::new(queue);
+        let (results, completed) = mpsc::channel::<(u64, ResponseEvent)>();
+        let (slots, released) = mpsc::sync_channel::<()>(self.max_in_flight.max(self.concurrency));
+        let closed = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                let results = results.clone();
+                let queue = &queue;
+                scope.spawn(move || loop {
+                    let job = queue.lock().unwrap().recv();
+                    match job {
+                        Ok((seq, line)) => {
+                            let _ = results.send((seq, self.respond(processor, line)));
+                        }
+                        Err(_) => break,
+                    }
+                });
+            }
+            drop(results);
+
+            let closed = &closed;
+            let output = scope.spawn(move || {
+                let mut pending = BTreeMap::new();
+                let mut next = 0;
+                for (seq, response) in completed {
+                    pending.insert(seq, response);
+                    while let Some(response) = pending.remove(&next) {
+                        if let Err(err) = write_response(&mut writer, &response) {
+                            closed.store(true, Ordering::SeqCst);
+                            return Err(err);
+                        }
+                        let _ = released.recv();
+                        next += 1;
+                    }
+                }
+                Ok(())
+            });
+
+            let mut input = Ok(());
+            let mut seq = 0;
+            while !closed.load(Ordering::SeqCst) {
+                match self.read_line(&mut reader) {
+                    Ok(Some(line)) => {
+                        if slots.send(()).is_err() || jobs.send((seq, line)).is_err() {
+                            break;
+                        }
+                        seq += 1;
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        input = Err(err);
+                        break;
+                    }
+                }
+            }
+            drop(jobs);
+
+            let output = output.join().unwrap();
+            input.and(output)
+        })
+    }
+
+    fn respond<S: ?Sized + ProcessorStore>(
+        &self,
+        processor: &EventProcessor<S>,
+        line: Line,
+    ) -> ResponseEvent {
+        match line {
+            Line::Event(payload) => processor.process_event(&payload),
+            Line::TooLarge => message_too_large(self.max_line_bytes),
+        }
+    }
+
+    fn read_line<R: BufRead>(&self, reader: &mut R) -> io::Result<Option<Line>> {
+        loop {
+            let mut buf = vec![];
+            let read = Read::take(&mut *reader, self.max_line_bytes as u64 + 1)
+                .read_until(b'\n', &mut buf)?;
+            if read == 0 {
+                return Ok(None);
+            }
+
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+                if buf.last() == Some(&b'\r') {
+                    buf.pop();
+                }
+            } else if buf.len() > self.max_line_bytes {
+                skip_line(reader)?;
+                return Ok(Some(Line::TooLarge));
+            }
+
+            let line = String::from_utf8_lossy(&buf);
+            if !line.trim().is_empty() {
+                return Ok(Some(Line::Event(line.into_owned())));
+            }
+        }
+    }
+}
+
+impl Default for StdioTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn skip_line<R: BufRead>(reader: &mut R) -> io::Result<()> {
+    loop {
+        let (consumed, done) = {
+            let available = reader.fill_buf()?;
+            if available.is_empty() {
+                return Ok(());
+            }
+            match available.iter().position(|byte| *byte == b'\n') {
+                Some(idx) => (idx + 1, true),
+                None => (available.len(), false),
+            }
+        };
+        reader.consume(consumed);
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &ResponseEvent) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, response)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::events::{response_for, ResponseEvent};
+    use crate::processor::{EventProcessor, SyncEventProcessor};
+    use crate::store::SimpleEventStore;
+    use crate::transport::stdio::StdioTransport;
+
+    fn event(id: u32, delay: u64) -> String {
+        format!(
+            r#"{{"name":"event:test","version":1,"id":"f467e03c-abab-4c2f-b4cf-{:012}","flowId":"cb745ef4-863b-41c4-99c7-325fe2b2b7f8","payload":{{"delay":{}}},"metadata":{{}},"identity":{{}},"auth":{{}}}}"#,
+            id, delay
+        )
+    }
+
+    fn processor() -> SyncEventProcessor {
+        let mut store = SimpleEventStore::new_sync();
+        store.add("event:test", 1, |req| {
+            thread::sleep(Duration::from_millis(
+                req.payload["delay"].as_u64().unwrap(),
+            ));
+            Ok(response_for(req, "ok"))
+        });
+        EventProcessor::new_sync(Box::new(store))
+    }
+
+    fn serve(transport: StdioTransport, input: &str) -> Vec<ResponseEvent> {
+        let mut output = vec![];
+        transport
+            .serve(&processor(), Cursor::new(input.as_bytes()), &mut output)
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_serves_lines_until_eof() {
+        let input = format!(
+            "{}\n\n{}\r\nnot json\n{}",
+            event(1, 0),
+            event(2, 0),
+            event(3, 0)
+        );
+
+        let responses = serve(StdioTransport::new(), &input);
+
+        let names: Vec<_> = responses.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            vec![
+                "event:test:response",
+                "event:test:response",
+                "badProtocol",
+                "event:test:response"
+            ],
+            names
+        );
+        assert!(responses[3].id.to_string().ends_with("000000000003"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_lines_and_continues() {
+        let input = format!("{}\n{}\n", "x".repeat(4096), event(1, 0));
+
+        let responses = serve(StdioTransport::new().with_max_line_bytes(1024), &input);
+
+        assert_eq!(2, responses.len());
+        let error = responses[0].get_error();
+        assert_eq!("MESSAGE_TOO_LARGE", error.code());
+        assert_eq!(1024, error.parameters()["maxBytes"]);
+        assert!(responses[1].is_success());
+    }
+
+    #[test]
+    fn test_concurrent_processing_preserves_order() {
+        let input: Vec<_> = (0..8).map(|idx| event(idx, (8 - idx as u64) * 5)).collect();
+
+        let responses = serve(StdioTransport::new().with_concurrency(4), &input.join("\n"));
+
+        let ids: Vec<_> = responses
+            .iter()
+            .map(|r| r.id.to_string()[24..].parse::<u32>().unwrap())
+            .collect();
+        assert_eq!((0..8).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn test_limits_items_waiting_behind_a_slow_one() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let started_when_head_finished = Arc::new(AtomicUsize::new(0));
+        let mut store = SimpleEventStore::new();
+        let (counter, observed) = (started.clone(), started_when_head_finished.clone());
+        store.add("event:test", 1, move |req| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if req.payload["delay"] != 0 {
+                thread::sleep(Duration::from_millis(100));
+                observed.store(counter.load(Ordering::SeqCst), Ordering::SeqCst);
+            }
+            Ok(response_for(req, "ok"))
+        });
+        let input: Vec<_> = (0..32).map(|idx| event(idx, u64::from(idx == 0))).collect();
+
+        let mut output = vec![];
+        StdioTransport::new()
+            .with_concurrency(2)
+            .with_max_in_flight(4)
+            .serve(
+                &EventProcessor::new(Box::new(store)),
+                Cursor::new(input.join("\n").into_bytes()),
+                &mut output,
+            )
+            .unwrap();
+
+        assert_eq!(32, String::from_utf8(output).unwrap().lines().count());
+        assert_eq!(32, started.load(Ordering::SeqCst));
+        assert!(started_when_head_finished.load(Ordering::SeqCst) <= 4);
+    }
+}
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::protocol::Role;
+use tungstenite::{Error, Message, WebSocket};
+
+use crate::events::RequestEvent;
+use crate::processor::SyncEventProcessor;
+use crate::transport::ShutdownHandle;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Identify = dyn Fn(&Handshake) -> Option<Value> + Send + Sync;
+
+#[derive(Debug, Clone)]
+pub struct Handshake {
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HashMap<String, String>,
+}
+
+impl Handshake {
+    fn from_request(request: &Request) -> Self {
+        Handshake {
+            path: String::from(request.uri().path()),
+            query: request.uri().query().map(String::from),
+            headers: request
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|value| (String::from(name.as_str()), String::from(value)))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+enum Outgoing {
+    Message(Message),
+    Bytes(Vec<u8>),
+    Stop,
+}
+
+// Reads from the connection and hands every write to the writer thread, so that frames
+// written while reading (handshake, pongs, close) are never interleaved with responses.
+struct Inbound {
+    stream: TcpStream,
+    outgoing: Sender<Outgoing>,
+}
+
+impl Read for Inbound {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Inbound {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing
+            .send(Outgoing::Bytes(buf.to_vec()))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Authenticate<'a> {
+    identify: &'a Identify,
+    identity: &'a mut Option<Value>,
+}
+
+impl Callback for Authenticate<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        match (self.identify)(&Handshake::from_request(request)) {
+            Some(value) => {
+                *self.identity = Some(value);
+                Ok(response)
+            }
+            None => Err(rejection()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionInfo {
+    pub id: u64,
+    pub identity: Value,
+}
+
+#[derive(Default)]
+struct Registry {
+    connections: Mutex<HashMap<u64, (Value, Sender<Outgoing>)>>,
+}
+
+#[derive(Clone)]
+pub struct Notifier {
+    registry: Arc<Registry>,
+}
+
+impl Notifier {
+    pub fn connections(&self) -> Vec<ConnectionInfo> {
+        let mut connections: Vec<_> = self
+            .registry
+            .connections
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, (identity, _))| ConnectionInfo {
+                id: *id,
+                identity: identity.clone(),
+            })
+            .collect();
+        connections.sort_by_key(|connection| connection.id);
+        connections
+    }
+
+    pub fn notify(&self, connection: u64, event: &RequestEvent) -> bool {
+        let message = Message::Text(serde_json::to_string(event).unwrap());
+        match self.registry.connections.lock().unwrap().get(&connection) {
+            Some((_, outgoing)) => outgoing.send(Outgoing::Message(message)).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn notify_where<F>(&self, filter: F, event: &RequestEvent) -> usize
+    where
+        F: Fn(&Value) -> bool,
+    {
+        let message = Message::Text(serde_json::to_string(event).unwrap());
+        self.registry
+            .connections
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|(identity, _)| filter(identity))
+            .filter(|(_, outgoing)| outgoing.send(Outgoing::Message(message.clone())).is_ok())
+            .count()
+    }
+
+    pub fn broadcast(&self, event: &RequestEvent) -> usize {
+        self.notify_where(|_| true, event)
+    }
+}
+
+pub struct WebSocketServer {
+    identify: Box<Identify>,
+    concurrency: usize,
+    registry: Arc<Registry>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl WebSocketServer {
+    pub fn new() -> Self {
+        WebSocketServer {
+            identify: Box::new(|_| Some(json!({}))),
+            concurrency: 8,
+            registry: Arc::new(Registry::default()),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_identity<F>(mut self, identify: F) -> Self
+    where
+        F: Fn(&Handshake) -> Option<Value> + Send + Sync + 'static,
+    {
+        self.identify = Box::new(identify);
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn notifier(&self) -> Notifier {
+        Notifier {
+            registry: self.registry.clone(),
+        }
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(self.shutdown.clone())
+    }
+
+    pub fn serve(&self, processor: &SyncEventProcessor, listener: TcpListener) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+
+        thread::scope(|scope| {
+            let mut next_id = 0u64;
+            loop {
+                if self.shutdown.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let id = next_id;
+                        next_id += 1;
+                        scope.spawn(move || {
+                            if let Err(err) = self.serve_connection(processor, stream, id) {
+                                log::warn!("WebSocket connection closed with error: {}", err);
+                            }
+                            self.registry.connections.lock().unwrap().remove(&id);
+                        });
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(POLL_INTERVAL)
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        })
+    }
+
+    fn serve_connection(
+        &self,
+        processor: &SyncEventProcessor,
+        stream: TcpStream,
+        id: u64,
+    ) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let (outgoing, outbox) = mpsc::channel();
+        let raw = stream.try_clone()?;
+
+        thread::scope(|scope| {
+            let writer = scope.spawn(move || write_outgoing(raw, outbox));
+            let inbound = Inbound {
+                stream,
+                outgoing: outgoing.clone(),
+            };
+            let result = self.read_connection(processor, inbound, &outgoing, id);
+            self.registry.connections.lock().unwrap().remove(&id);
+            let _ = outgoing.send(Outgoing::Stop);
+            let written = writer.join().unwrap();
+            result.and(written)
+        })
+    }
+
+    fn read_connection(
+        &self,
+        processor: &SyncEventProcessor,
+        inbound: Inbound,
+        outgoing: &Sender<Outgoing>,
+        id: u64,
+    ) -> io::Result<()> {
+        let mut identity = None;
+        let authenticate = Authenticate {
+            identify: self.identify.as_ref(),
+            identity: &mut identity,
+        };
+        let mut socket = tungstenite::accept_hdr(inbound, authenticate)
+            .map_err(|err| io::Error::other(format!("handshake failed: {}", err)))?;
+        let identity = match identity {
+            Some(identity) => identity,
+            None => return Ok(()),
+        };
+        socket
+            .get_ref()
+            .stream
+            .set_read_timeout(Some(POLL_INTERVAL))?;
+
+        self.registry
+            .connections
+            .lock()
+            .unwrap()
+            .insert(id, (identity.clone(), outgoing.clone()));
+
+        let (jobs, queue) = mpsc::sync_channel::<String>(self.concurrency);
+        let queue = Mutex::new(queue);
+        let in_flight = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..self.concurrency {
+                let (queue, identity, in_flight) = (&queue, &identity, &in_flight);
+                let outgoing = outgoing.clone();
+                scope.spawn(move || loop {
+                    let payload = match queue.lock().unwrap().recv() {
+                        Ok(payload) => payload,
+                        Err(_) => break,
+                    };
+                    let response = processor.process_event(&inject_identity(&payload, identity));
+                    let message = Message::Text(serde_json::to_string(&response).unwrap());
+                    let _ = outgoing.send(Outgoing::Message(message));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+
+            let mut jobs = Some(jobs);
+            loop {
+                if self.shutdown.load(Ordering::SeqCst) {
+                    jobs = None;
+                }
+                if jobs.is_none() && in_flight.load(Ordering::SeqCst) == 0 {
+                    let _ = socket.close(None);
+                    let _ = socket.flush();
+                    return Ok(());
+                }
+
+                let payload = match socket.read() {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Binary(bytes)) => String::from_utf8_lossy(&bytes).into_owned(),
+                    Ok(_) => continue,
+                    Err(Error::Io(err))
+                        if err.kind() == io::ErrorKind::WouldBlock
+                            || err.kind() == io::ErrorKind::TimedOut =>
+                    {
+                        continue
+                    }
+                    Err(Error::ConnectionClosed) | Err(Error::AlreadyClosed) => return Ok(()),
+                    Err(err) => return Err(into_io(err)),
+                };
+                if let Some(jobs) = &jobs {
+                    in_flight.fetch_add(1, Ordering::SeqCst);
+                    let _ = jobs.send(payload);
+                }
+            }
+        })
+    }
+}
+
+fn write_outgoing(stream: TcpStream, outbox: Receiver<Outgoing>) -> io::Result<()> {
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    for outgoing in outbox.iter() {
+        let result = match outgoing {
+            Outgoing::Message(message) => socket.send(message).map_err(into_io),
+            Outgoing::Bytes(bytes) => socket
+                .get_mut()
+                .write_all(&bytes)
+                .and_then(|_| socket.get_mut().flush()),
+            Outgoing::Stop => return Ok(()),
+        };
+        if let Err(err) = result {
+            let _ = socket.get_ref().shutdown(Shutdown::Both);
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+impl Default for WebSocketServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn inject_identity(payload: &str, identity: &Value) -> String {
+    match serde_json::from_str(payload) {
+        Ok(Value::Object(mut event)) => {
+            event.insert(String::from("identity"), identity.clone());
+            Value::Object(event).to_string()
+        }
+        _ => String::from(payload),
+    }
+}
+
+fn rejection() -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(String::from("unauthorized")));
+    *response.status_mut() = StatusCode::UNAUTHORIZED;
+    response
+}
+
+fn into_io(err: Error) -> io::Error {
+    match err {
+        Error::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+    use tungstenite::client::IntoClientRequest;
+    use tungstenite::stream::MaybeTlsStream;
+    use tungstenite::{Message, WebSocket};
+
+    use crate::events::{parse_event, response_for, RequestEvent, ResponseEvent};
+    use crate::processor::{EventProcessor, SyncEventProcessor};
+    use crate::store::SimpleEventStore;
+    use crate::transport::websocket::{WebSocketServer, POLL_INTERVAL};
+
+    fn processor() -> SyncEventProcessor {
+        let mut store = SimpleEventStore::new_sync();
+        store.add("whoami", 1, |req| {
+            Ok(response_for(req, req.identity.clone()))
+        });
+        EventProcessor::new_sync(Box::new(store))
+    }
+
+    fn server() -> WebSocketServer {
+        WebSocketServer::new().with_identity(|handshake| {
+            handshake
+                .header("Authorization")
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(|user| json!({ "user": user }))
+        })
+    }
+
+    fn event(name: &str) -> RequestEvent {
+        let mut event = parse_event(
+            r#"{
+                "name": "whoami",
+                "version": 1,
+                "id": "f467e03c-abab-4c2f-b4cf-4871fd349c6e",
+                "flowId": "cb745ef4-863b-41c4-99c7-325fe2b2b7f8",
+                "payload": {},
+                "metadata": {},
+                "identity": { "user": "mallory" },
+                "auth": {}
+            }"#,
+        )
+        .unwrap();
+        event.name = String::from(name);
+        event
+    }
+
+    fn connect(
+        address: SocketAddr,
+        token: &str,
+    ) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, Box<tungstenite::Error>> {
+        let mut request = format!("ws://{}/events", address)
+            .into_client_request()
+            .unwrap();
+        request
+            .headers_mut()
+            .insert("Authorization", token.parse().unwrap());
+        tungstenite::connect(request)
+            .map(|(socket, _)| socket)
+            .map_err(Box::new)
+    }
+
+    fn receive<T: serde::de::DeserializeOwned>(
+        socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    ) -> T {
+        match socket.read().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_injects_handshake_identity_and_pushes_notifications() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = server();
+        let notifier = server.notifier();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let mut socket = connect(address, "Bearer alice").unwrap();
+            let request = event("whoami");
+            socket
+                .send(Message::Text(serde_json::to_string(&request).unwrap()))
+                .unwrap();
+            let response: ResponseEvent = receive(&mut socket);
+            assert_eq!(request.id, response.id);
+            assert_eq!(json!({ "user": "alice" }), response.payload);
+
+            let connections = notifier.connections();
+            assert_eq!(1, connections.len());
+            assert_eq!(json!({ "user": "alice" }), connections[0].identity);
+            assert_eq!(
+                1,
+                notifier.notify_where(|identity| identity["user"] == "alice", &event("ping"))
+            );
+            let notification: RequestEvent = receive(&mut socket);
+            assert_eq!("ping", notification.name);
+
+            shutdown.shutdown();
+            while socket.read().is_ok() {}
+        });
+        assert!(notifier.connections().is_empty());
+    }
+
+    #[test]
+    fn test_batch_items_cannot_override_the_handshake_identity() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = server();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let mut socket = connect(address, "Bearer alice").unwrap();
+            let mut item = serde_json::to_value(event("whoami")).unwrap();
+            item["identity"] = json!({ "user": "admin" });
+            let mut batch = event("batch");
+            batch.payload = json!({ "events": [item] });
+            socket
+                .send(Message::Text(serde_json::to_string(&batch).unwrap()))
+                .unwrap();
+            let response: ResponseEvent = receive(&mut socket);
+            assert_eq!("batch:response", response.name);
+            assert_eq!(
+                json!({ "user": "alice" }),
+                response.payload["responses"][0]["payload"]
+            );
+
+            shutdown.shutdown();
+            while socket.read().is_ok() {}
+        });
+    }
+
+    #[test]
+    fn test_replies_without_waiting_for_the_read_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = server();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            let mut socket = connect(address, "Bearer alice").unwrap();
+            let started = Instant::now();
+            for _ in 0..20 {
+                let request = event("whoami");
+                socket
+                    .send(Message::Text(serde_json::to_string(&request).unwrap()))
+                    .unwrap();
+                let response: ResponseEvent = receive(&mut socket);
+                assert_eq!(request.id, response.id);
+            }
+            assert!(started.elapsed() < POLL_INTERVAL * 10);
+
+            shutdown.shutdown();
+            while socket.read().is_ok() {}
+        });
+    }
+
+    #[test]
+    fn test_rejects_unidentified_handshakes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = server();
+        let shutdown = server.shutdown_handle();
+        let processor = processor();
+
+        thread::scope(|scope| {
+            scope.spawn(|| server.serve(&processor, listener).unwrap());
+
+            match connect(address, "Basic bob").map_err(|err| *err) {
+                Err(tungstenite::Error::Http(response)) => assert_eq!(401, response.status()),
+                other => panic!("unexpected handshake result: {:?}", other.map(|_| ())),
+            }
+
+            thread::sleep(Duration::from_millis(10));
+            shutdown.shutdown();
+        });
+    }
+}
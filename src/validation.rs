@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Deserializer, Map, Value};
+use uuid::Uuid;
+
+use crate::errors::{ERROR_TYPES, PROTOCOL_ERROR_TYPES};
+use crate::schema::Violation;
+
+const FIELDS: [&str; 8] = [
+    "name", "version", "id", "flowId", "payload", "identity", "auth", "metadata",
+];
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    Request,
+    Response,
+}
+
+impl EventKind {
+    pub fn detect(event: &Value) -> Self {
+        let suffix = event
+            .get("name")
+            .and_then(Value::as_str)
+            .and_then(|name| name.rfind(':').map(|idx| &name[idx + 1..]));
+        match suffix {
+            Some(suffix)
+                if suffix == "response"
+                    || ERROR_TYPES.contains(&suffix)
+                    || PROTOCOL_ERROR_TYPES.contains(&suffix) =>
+            {
+                EventKind::Response
+            }
+            _ => EventKind::Request,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    pub pointer: String,
+    pub message: String,
+}
+
+pub fn validate_request(event: &Value) -> Vec<Violation> {
+    validate_envelope(event)
+}
+
+pub fn validate_response(event: &Value) -> Vec<Violation> {
+    let mut violations = validate_envelope(event);
+    let name = match event.get("name").and_then(Value::as_str) {
+        Some(name) => name,
+        None => return violations,
+    };
+    if !name.contains(':') {
+        violations.push(violation(
+            "/name",
+            "response name must end with ':response' or ':<errorType>'",
+        ));
+    } else if !name.ends_with(":response") {
+        match event.get("payload") {
+            Some(Value::Object(payload)) => {
+                if !payload.get("code").is_some_and(Value::is_string) {
+                    violations.push(violation(
+                        "/payload/code",
+                        "error responses require a string code",
+                    ));
+                }
+                if !payload.contains_key("parameters") {
+                    violations.push(violation(
+                        "/payload/parameters",
+                        "error responses require parameters",
+                    ));
+                }
+            }
+            Some(_) => violations.push(violation(
+                "/payload",
+                "error responses require an object payload",
+            )),
+            None => {}
+        }
+    }
+    violations
+}
+
+pub fn validate(event: &Value, kind: EventKind) -> Vec<Violation> {
+    match kind {
+        EventKind::Request => validate_request(event),
+        EventKind::Response => validate_response(event),
+    }
+}
+
+pub fn validate_document(contents: &str, kind: Option<EventKind>) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut events = 0;
+    let mut offset = 0;
+
+    while offset < contents.len() {
+        let rest = &contents[offset..];
+        let start = offset + (rest.len() - rest.trim_start().len());
+        if start == contents.len() {
+            break;
+        }
+
+        let mut stream = Deserializer::from_str(&contents[start..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(event)) => {
+                events += 1;
+                let end = start + stream.byte_offset();
+                let spans = spans(&contents[start..end]);
+                let kind = kind.unwrap_or_else(|| EventKind::detect(&event));
+                problems.extend(validate(&event, kind).into_iter().map(|v| {
+                    let (line, column) = position(contents, start + span_of(&spans, &v.pointer));
+                    Problem {
+                        line,
+                        column,
+                        pointer: v.pointer,
+                        message: v.message,
+                    }
+                }));
+                offset = end;
+            }
+            Some(Err(err)) => {
+                let (line, column) = position(contents, start);
+                problems.push(Problem {
+                    line: line + err.line() - 1,
+                    column: if err.line() == 1 {
+                        column + err.column() - 1
+                    } else {
+                        err.column()
+                    },
+                    pointer: String::new(),
+                    message: strip_position(&err),
+                });
+                offset = next_top_level_line(contents, start);
+            }
+            None => break,
+        }
+    }
+
+    if events == 0 && problems.is_empty() {
+        problems.push(Problem {
+            line: 1,
+            column: 1,
+            pointer: String::new(),
+            message: String::from("no events found"),
+        });
+    }
+    problems
+}
+
+fn validate_envelope(event: &Value) -> Vec<Violation> {
+    let object = match event {
+        Value::Object(object) => object,
+        _ => return vec![violation("", "expected an event object")],
+    };
+
+    let mut violations = vec![];
+    for field in FIELDS.iter() {
+        if !object.contains_key(*field) {
+            violations.push(violation("", &format!("missing field '{}'", field)));
+        }
+    }
+    for field in object.keys() {
+        if !FIELDS.contains(&field.as_str()) {
+            violations.push(violation(
+                &format!("/{}", escape(field)),
+                &format!("unknown field '{}'", field),
+            ));
+        }
+    }
+
+    match object.get("name") {
+        Some(Value::String(name)) if name.is_empty() => {
+            violations.push(violation("/name", "name must not be empty"))
+        }
+        Some(Value::String(name)) if name.chars().any(char::is_whitespace) => {
+            violations.push(violation("/name", "name must not contain whitespace"))
+        }
+        Some(Value::String(_)) | None => {}
+        Some(_) => violations.push(violation("/name", "name must be a string")),
+    }
+    if let Some(version) = object.get("version") {
+        if version
+            .as_u64()
+            .is_none_or(|version| version > u64::from(u16::MAX))
+        {
+            violations.push(violation(
+                "/version",
+                "version must be an integer between 0 and 65535",
+            ));
+        }
+    }
+    check_uuid(object, "id", &mut violations);
+    check_uuid(object, "flowId", &mut violations);
+    for field in ["identity", "auth", "metadata"].iter() {
+        if object.get(*field).is_some_and(|value| !value.is_object()) {
+            violations.push(violation(
+                &format!("/{}", field),
+                &format!("{} must be an object", field),
+            ));
+        }
+    }
+    violations
+}
+
+fn check_uuid(object: &Map<String, Value>, field: &str, violations: &mut Vec<Violation>) {
+    if let Some(value) = object.get(field) {
+        if value
+            .as_str()
+            .is_none_or(|value| Uuid::parse_str(value).is_err())
+        {
+            violations.push(violation(
+                &format!("/{}", field),
+                &format!("{} must be a UUID", field),
+            ));
+        }
+    }
+}
+
+fn violation(pointer: &str, message: &str) -> Violation {
+    Violation {
+        pointer: String::from(pointer),
+        message: String::from(message),
+    }
+}
+
+fn escape(field: &str) -> String {
+    field.replace('~', "~0").replace('/', "~1")
+}
+
+fn position(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn span_of(spans: &HashMap<String, usize>, pointer: &str) -> usize {
+    let mut pointer = pointer;
+    loop {
+        if let Some(offset) = spans.get(pointer) {
+            return *offset;
+        }
+        match pointer.rfind('/') {
+            Some(idx) => pointer = &pointer[..idx],
+            None => return 0,
+        }
+    }
+}
+
+// Maps the JSON pointer of every member to the offset of its key, and of every array
+// element to the offset of its value. The text has already been parsed by serde_json.
+fn spans(text: &str) -> HashMap<String, usize> {
+    let mut spans = HashMap::new();
+    spans.insert(String::new(), 0);
+    scan_value(text.as_bytes(), &mut 0, "", &mut spans);
+    spans
+}
+
+fn scan_value(bytes: &[u8], idx: &mut usize, pointer: &str, spans: &mut HashMap<String, usize>) {
+    skip_whitespace(bytes, idx);
+    match bytes.get(*idx) {
+        Some(b'{') => {
+            *idx += 1;
+            loop {
+                skip_whitespace(bytes, idx);
+                match bytes.get(*idx) {
+                    Some(b'}') | None => break,
+                    Some(b',') => *idx += 1,
+                    Some(_) => {
+                        let key_start = *idx;
+                        scan_string(bytes, idx);
+                        let key: String =
+                            serde_json::from_slice(&bytes[key_start..*idx]).unwrap_or_default();
+                        skip_whitespace(bytes, idx);
+                        *idx += 1;
+                        let child = format!("{}/{}", pointer, escape(&key));
+                        spans.insert(child.clone(), key_start);
+                        scan_value(bytes, idx, &child, spans);
+                    }
+                }
+            }
+            *idx += 1;
+        }
+        Some(b'[') => {
+            *idx += 1;
+            let mut element = 0;
+            loop {
+                skip_whitespace(bytes, idx);
+                match bytes.get(*idx) {
+                    Some(b']') | None => break,
+                    Some(b',') => *idx += 1,
+                    Some(_) => {
+                        let child = format!("{}/{}", pointer, element);
+                        spans.insert(child.clone(), *idx);
+                        scan_value(bytes, idx, &child, spans);
+                        element += 1;
+                    }
+                }
+            }
+            *idx += 1;
+        }
+        Some(b'"') => scan_string(bytes, idx),
+        Some(_) => {
+            while bytes.get(*idx).is_some_and(|b| !b",}] \t\r\n".contains(b)) {
+                *idx += 1;
+            }
+        }
+        None => {}
+    }
+}
+
+fn scan_string(bytes: &[u8], idx: &mut usize) {
+    *idx += 1;
+    while let Some(b) = bytes.get(*idx) {
+        *idx += if *b == b'\\' { 2 } else { 1 };
+        if *b == b'"' {
+            break;
+        }
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], idx: &mut usize) {
+    while bytes.get(*idx).is_some_and(u8::is_ascii_whitespace) {
+        *idx += 1;
+    }
+}
+
+fn strip_position(err: &serde_json::Error) -> String {
+    let message = format!("{}", err);
+    match message.rfind(" at line ") {
+        Some(idx) => String::from(&message[..idx]),
+        None => message,
+    }
+}
+
+fn next_top_level_line(contents: &str, from: usize) -> usize {
+    let mut offset = from;
+    while let Some(idx) = contents[offset..].find('\n') {
+        offset += idx + 1;
+        if contents[offset..].starts_with('{') {
+            return offset;
+        }
+    }
+    contents.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::validation::{
+        validate_document, validate_request, validate_response, EventKind, Problem,
+    };
+
+    const REQUEST: &str = r#"{"name":"account:get","version":1,"id":"f467e03c-abab-4c2f-b4cf-4871fd349c6e","flowId":"cb745ef4-863b-41c4-99c7-325fe2b2b7f8","payload":{},"identity":{},"auth":{},"metadata":{}}"#;
+
+    #[test]
+    fn test_accepts_valid_request() {
+        let event = serde_json::from_str(REQUEST).unwrap();
+
+        assert!(validate_request(&event).is_empty());
+        assert_eq!(EventKind::Request, EventKind::detect(&event));
+    }
+
+    #[test]
+    fn test_reports_envelope_violations() {
+        let event = json!({
+            "name": "account get",
+            "version": 70000,
+            "id": "not-a-uuid",
+            "payload": {},
+            "identity": [],
+            "auth": {},
+            "metadata": {},
+            "extra": true
+        });
+
+        let messages: Vec<_> = validate_request(&event)
+            .into_iter()
+            .map(|v| format!("{} {}", v.pointer, v.message))
+            .collect();
+
+        assert_eq!(
+            vec![
+                " missing field 'flowId'",
+                "/extra unknown field 'extra'",
+                "/name name must not contain whitespace",
+                "/version version must be an integer between 0 and 65535",
+                "/id id must be a UUID",
+                "/identity identity must be an object",
+            ],
+            messages
+        );
+    }
+
+    #[test]
+    fn test_requires_code_and_parameters_on_error_responses() {
+        let mut event: serde_json::Value = serde_json::from_str(REQUEST).unwrap();
+        event["name"] = json!("account:get:notFound");
+
+        assert_eq!(EventKind::Response, EventKind::detect(&event));
+        let pointers: Vec<_> = validate_response(&event)
+            .into_iter()
+            .map(|v| v.pointer)
+            .collect();
+        assert_eq!(vec!["/payload/code", "/payload/parameters"], pointers);
+    }
+
+    #[test]
+    fn test_reports_positions_in_json_lines() {
+        let contents = format!(
+            "{}\n{{\"name\": }}\n{}\n",
+            REQUEST,
+            REQUEST.replace("\"version\":1", "\"version\":\"1\"")
+        );
+
+        assert_eq!(
+            vec![
+                Problem {
+                    line: 2,
+                    column: 10,
+                    pointer: String::new(),
+                    message: String::from("expected value"),
+                },
+                Problem {
+                    line: 3,
+                    column: 23,
+                    pointer: String::from("/version"),
+                    message: String::from("version must be an integer between 0 and 65535"),
+                },
+            ],
+            validate_document(&contents, None)
+        );
+    }
+
+    #[test]
+    fn test_reports_the_position_of_each_field() {
+        let mut event: serde_json::Value = serde_json::from_str(REQUEST).unwrap();
+        event["name"] = json!("account:get:notFound");
+        event["payload"] = json!({ "code": 404, "tags": ["a", "b\\\"c"] });
+        event["flowId"] = json!("flow");
+        let contents = format!("\n{}", serde_json::to_string_pretty(&event).unwrap());
+        let line_of = |needle: &str| {
+            contents
+                .lines()
+                .position(|line| line.trim_start().starts_with(needle))
+                .unwrap()
+                + 1
+        };
+
+        let positions: Vec<_> = validate_document(&contents, None)
+            .into_iter()
+            .map(|p| (p.pointer, p.line, p.column))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (String::from("/flowId"), line_of("\"flowId\""), 3),
+                (String::from("/payload/code"), line_of("\"code\""), 5),
+                (
+                    String::from("/payload/parameters"),
+                    line_of("\"payload\""),
+                    3
+                ),
+            ],
+            positions
+        );
+    }
+
+    #[test]
+    fn test_validates_pretty_printed_documents() {
+        let event: serde_json::Value = serde_json::from_str(REQUEST).unwrap();
+        let contents = serde_json::to_string_pretty(&event).unwrap();
+
+        assert!(validate_document(&contents, Some(EventKind::Request)).is_empty());
+        assert_eq!(
+            "no events found",
+            validate_document("  \n", None)[0].message
+        );
+    }
+}